@@ -0,0 +1,149 @@
+//! Human-readable rendering of URI parse errors.
+//!
+//! ```text
+//! error: invalid percent-encoding in path at byte 19
+//!  | http://example.com/%zz
+//!  |                    ^^^
+//!  = help: did you mean %25? A literal '%' must be percent-encoded
+//! ```
+
+use std::{fmt, ops::Range};
+
+use crate::{Component, Error};
+
+impl Error {
+    /// Byte range of `input` covered by this error.
+    ///
+    /// `input` must be the string that produced the error.
+    pub fn span(&self, input: &str) -> Range<usize> {
+        let start = self.offset().min(input.len());
+        let rest = &input[start..];
+        let len = match self {
+            Self::InvalidPercentEncoding { .. } => {
+                // The '%' and whatever was supposed to be its two hex digits.
+                rest.char_indices().nth(3).map_or(rest.len(), |(i, _)| i)
+            }
+            Self::UnterminatedIpLiteral { .. } => rest.find(['/', '?', '#']).unwrap_or(rest.len()),
+            Self::InvalidIpLiteral { .. } => rest.find(']').map_or(rest.len(), |i| i + 1),
+            Self::PortOutOfRange { .. } => rest
+                .find(|ch: char| !ch.is_ascii_digit())
+                .unwrap_or(rest.len()),
+            Self::EmptyScheme { .. }
+            | Self::InvalidSchemeChar { .. }
+            | Self::InvalidChar { .. } => rest.chars().next().map_or(0, char::len_utf8),
+        };
+        start..start + len
+    }
+
+    /// A suggestion for fixing the input, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        Some(match *self {
+            Self::EmptyScheme { .. } => {
+                "a relative reference can't start with ':'; prefix it with \"./\"".to_string()
+            }
+            Self::InvalidSchemeChar { .. } => {
+                "a scheme may only contain letters, digits, '+', '-' and '.', starting with a \
+                 letter; prefix a relative path containing ':' with \"./\""
+                    .to_string()
+            }
+            Self::InvalidChar {
+                component: Component::Port,
+                ..
+            } => "a port may only contain the digits 0-9".to_string(),
+            Self::InvalidChar { ch: ' ', .. } => {
+                "spaces must be percent-encoded as %20".to_string()
+            }
+            Self::InvalidChar { ch, .. } => {
+                let mut buf = [0; 4];
+                let encoded: String = ch
+                    .encode_utf8(&mut buf)
+                    .bytes()
+                    .map(|b| format!("%{b:02X}"))
+                    .collect();
+                format!("{ch:?} must be percent-encoded as {encoded}")
+            }
+            Self::InvalidPercentEncoding { .. } => {
+                "did you mean %25? A literal '%' must be percent-encoded".to_string()
+            }
+            Self::UnterminatedIpLiteral { .. } => "add the closing ']'".to_string(),
+            Self::InvalidIpLiteral { .. } => {
+                "expected an IPv6 address or an IPvFuture literal like \"v1.addr\"".to_string()
+            }
+            Self::PortOutOfRange { .. } => "a port must be between 0 and 65535".to_string(),
+        })
+    }
+
+    /// Render this error against the `input` that produced it, underlining the
+    /// failing span.
+    pub fn diagnostic<'a>(&'a self, input: &'a str) -> Diagnostic<'a> {
+        Diagnostic { error: self, input }
+    }
+}
+
+/// Caret-style rendering of an [`Error`], created by [`Error::diagnostic`].
+#[derive(Debug, Clone, Copy)]
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    input: &'a str,
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let span = self.error.span(self.input);
+        let mut line = String::new();
+        let mut underline = String::new();
+        for (i, ch) in self.input.char_indices() {
+            // Escape control characters so the underline stays aligned.
+            let before = line.chars().count();
+            if ch.is_control() {
+                line.extend(ch.escape_debug());
+            } else {
+                line.push(ch);
+            }
+            let width = line.chars().count() - before;
+            let marker = if span.contains(&i) { '^' } else { ' ' };
+            underline.extend(std::iter::repeat_n(marker, width));
+        }
+        if span.is_empty() {
+            // Point just past the end of the input.
+            underline.push('^');
+        }
+
+        writeln!(f, "error: {}", self.error)?;
+        writeln!(f, " | {line}")?;
+        write!(f, " | {}", underline.trim_end())?;
+        if let Some(hint) = self.error.hint() {
+            write!(f, "\n = help: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Uri;
+
+    fn render(input: &str) -> String {
+        Uri::new(input).unwrap_err().diagnostic(input).to_string()
+    }
+
+    #[test]
+    fn caret() {
+        assert_eq!(
+            render("http://example.com/%zz"),
+            "error: invalid percent-encoding in path at byte 19\n | http://example.com/%zz\n |                    ^^^\n = help: did you mean %25? A literal '%' must be percent-encoded"
+        );
+        assert_eq!(
+            render("http://[::1/x"),
+            "error: unterminated IP literal at byte 7\n | http://[::1/x\n |        ^^^^\n = help: add the closing ']'"
+        );
+        assert_eq!(
+            render("/é/a b"),
+            "error: invalid character 'é' in path at byte 1\n | /é/a b\n |  ^\n = help: 'é' must be percent-encoded as %C3%A9"
+        );
+        assert_eq!(
+            render("/\t/a%"),
+            "error: invalid character '\\t' in path at byte 1\n | /\\t/a%\n |  ^^\n = help: '\\t' must be percent-encoded as %09"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! `application/x-www-form-urlencoded` parsing and serialization as described
+//! in the [WHATWG URL standard].
+//!
+//! Unlike [`QueryParameters::parse`], `+` stands for a space and every pair
+//! has a value. This is the format of HTML form submissions, both as a
+//! request body and as the query of a `GET` form.
+//!
+//! ```
+//! use uri_rs::{Uri, form_urlencoded};
+//!
+//! let uri = Uri::new("/search?q=caf%C3%A9+au+lait&page=2").unwrap();
+//! let params = form_urlencoded::parse(uri.query.unwrap());
+//! assert_eq!(params.get("q"), Some(Some("café au lait")));
+//!
+//! let body = form_urlencoded::serialize([("q", "café au lait"), ("page", "2")]);
+//! assert_eq!(body, "q=caf%C3%A9+au+lait&page=2");
+//! ```
+//!
+//! [WHATWG URL standard]: https://url.spec.whatwg.org/#application/x-www-form-urlencoded
+
+use std::borrow::Cow;
+
+use crate::{
+    EncodeSet, QueryParameters, percent_decode_lossy, percent_encode_to, query::split_pairs,
+};
+
+/// Parse a form-urlencoded byte string, such as a request body or a query.
+///
+/// Empty pairs are skipped and a name without a `=` gets an empty value. Bad
+/// escapes are kept as-is and invalid UTF-8 is replaced with U+FFFD, so
+/// parsing never fails.
+///
+/// ```
+/// use uri_rs::form_urlencoded;
+///
+/// let params = form_urlencoded::parse(b"a=1+2&&b&c=%2B");
+/// assert_eq!(
+///     params.iter().collect::<Vec<_>>(),
+///     [("a", Some("1 2")), ("b", Some("")), ("c", Some("+"))]
+/// );
+/// ```
+pub fn parse(input: &(impl AsRef<[u8]> + ?Sized)) -> QueryParameters<'_> {
+    match String::from_utf8_lossy(input.as_ref()) {
+        Cow::Borrowed(input) => parse_str(input),
+        Cow::Owned(input) => parse_str(&input).into_owned(),
+    }
+}
+
+fn parse_str(input: &str) -> QueryParameters<'_> {
+    split_pairs(input, &['&'], '=')
+        .map(|(_, name, value)| (decode(name), Some(decode(value.unwrap_or_default()))))
+        .collect()
+}
+
+/// Serialize `name=value` pairs, encoding every byte but ASCII alphanumerics
+/// and `*-._`, and writing spaces as `+`.
+///
+/// ```
+/// use uri_rs::form_urlencoded;
+///
+/// assert_eq!(
+///     form_urlencoded::serialize([("a b", "1+1=2"), ("empty", "")]),
+///     "a+b=1%2B1%3D2&empty="
+/// );
+/// ```
+pub fn serialize<I, K, V>(pairs: I) -> String
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut out = String::new();
+    for (i, (name, value)) in pairs.into_iter().enumerate() {
+        if i > 0 {
+            out.push('&');
+        }
+        byte_serialize_to(name.as_ref(), &mut out);
+        out.push('=');
+        byte_serialize_to(value.as_ref(), &mut out);
+    }
+    out
+}
+
+/// Append `input` encoded with the `application/x-www-form-urlencoded` byte
+/// serializer.
+fn byte_serialize_to(input: &str, out: &mut String) {
+    for (i, chunk) in input.split(' ').enumerate() {
+        if i > 0 {
+            out.push('+');
+        }
+        percent_encode_to(chunk, &EncodeSet::FORM_URLENCODED, out);
+    }
+}
+
+/// Decode `+` as a space and `%XX` escapes, borrowing `s` if there is nothing
+/// to decode.
+fn decode(s: &str) -> Cow<'_, str> {
+    if !s.contains(['+', '%']) {
+        return Cow::Borrowed(s);
+    }
+    Cow::Owned(percent_decode_lossy(s.replace('+', " ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uri;
+
+    #[test]
+    fn parse_form() {
+        let params = parse("a=1&a=%32&b+c=d+e%2Bf&&=x&flag&e==&bad=%zz&%FF=%C3");
+        assert_eq!(
+            params.iter().collect::<Vec<_>>(),
+            [
+                ("a", Some("1")),
+                ("a", Some("2")),
+                ("b c", Some("d e+f")),
+                ("", Some("x")),
+                ("flag", Some("")),
+                ("e", Some("=")),
+                ("bad", Some("%zz")),
+                ("\u{FFFD}", Some("\u{FFFD}")),
+            ]
+        );
+        assert!(parse("").is_empty());
+        assert!(parse("&&").is_empty());
+        assert_eq!(
+            parse(b"a=\xFF+1&&b").iter().collect::<Vec<_>>(),
+            [("a", Some("\u{FFFD} 1")), ("b", Some(""))]
+        );
+
+        let borrowed = parse("plain=text");
+        assert!(borrowed.into_iter().all(|(k, v)| {
+            matches!(k, Cow::Borrowed(_)) && matches!(v, Some(Cow::Borrowed(_)))
+        }));
+
+        let uri = Uri::new("/?x=a+b&y=a%20b").unwrap();
+        assert_eq!(
+            uri.get_form_parameters().unwrap().get_all("x").next(),
+            Some(Some("a b"))
+        );
+        assert_eq!(
+            uri.get_query_parameters().unwrap().get("x"),
+            Some(Some("a+b"))
+        );
+    }
+
+    #[test]
+    fn serialize_form() {
+        let raw = "ünï *-._~!$'()+,;=:@/?&#%\n";
+        let encoded = serialize([(raw, raw)]);
+        assert_eq!(
+            encoded,
+            "%C3%BCn%C3%AF+*-._%7E%21%24%27%28%29%2B%2C%3B%3D%3A%40%2F%3F%26%23%25%0A=%C3%BCn%C3%AF+*-._%7E%21%24%27%28%29%2B%2C%3B%3D%3A%40%2F%3F%26%23%25%0A"
+        );
+        assert_eq!(parse(&encoded).get(raw), Some(Some(raw)));
+        assert_eq!(serialize(Vec::<(&str, &str)>::new()), "");
+
+        // Serialized forms are valid queries.
+        let uri = format!("/?{encoded}");
+        Uri::new(&uri).unwrap();
+    }
+}
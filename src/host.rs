@@ -0,0 +1,209 @@
+//! The `host` component of a URI, as described in [RFC 3986 section 3.2.2].
+//!
+//! [RFC 3986 section 3.2.2]: https://datatracker.ietf.org/doc/html/rfc3986#section-3.2.2
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{Error, Uri, UriOwned, parse};
+
+/// A parsed URI host.
+///
+/// ```
+/// use std::net::Ipv6Addr;
+///
+/// use uri_rs::{Host, Uri};
+///
+/// let uri = Uri::new("ldap://[2001:db8::7]/c=GB").unwrap();
+/// assert_eq!(uri.host, Some("[2001:db8::7]"));
+/// assert_eq!(
+///     uri.typed_host(),
+///     Some(Host::Ipv6 {
+///         addr: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 7),
+///         zone: None,
+///     })
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Host<'a> {
+    /// An `IPv4address` in dotted-decimal form.
+    Ipv4(Ipv4Addr),
+    /// An `IPv6address` in brackets, optionally followed by a zone
+    /// identifier as described in [RFC 6874].
+    ///
+    /// The zone is kept percent-encoded and without its `%25` delimiter.
+    ///
+    /// [RFC 6874]: https://datatracker.ietf.org/doc/html/rfc6874
+    Ipv6 {
+        addr: Ipv6Addr,
+        zone: Option<&'a str>,
+    },
+    /// An `IPvFuture` literal, `[v<version>.<address>]`.
+    IpvFuture { version: &'a str, address: &'a str },
+    /// A registered name, such as a DNS domain name. It may be
+    /// percent-encoded.
+    RegName(&'a str),
+}
+
+impl<'a> Host<'a> {
+    /// Parse a host as it appears in a URI, including the brackets around an
+    /// IP literal.
+    pub fn parse(s: &'a str) -> Result<Self, Error> {
+        parse::parse_host(s, 0)
+    }
+
+    /// The IP address of this host, if it is one.
+    pub fn ip(&self) -> Option<IpAddr> {
+        match *self {
+            Self::Ipv4(addr) => Some(addr.into()),
+            Self::Ipv6 { addr, .. } => Some(addr.into()),
+            Self::IpvFuture { .. } | Self::RegName(_) => None,
+        }
+    }
+
+    /// The percent-encoded zone identifier of a link-local IPv6 address.
+    ///
+    /// ```
+    /// use uri_rs::Uri;
+    ///
+    /// let uri = Uri::new("http://[fe80::1%25eth0]/").unwrap();
+    /// assert_eq!(uri.typed_host().unwrap().zone(), Some("eth0"));
+    /// ```
+    pub fn zone(&self) -> Option<&'a str> {
+        match *self {
+            Self::Ipv6 { zone, .. } => zone,
+            _ => None,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Host<'a> {
+    type Error = Error;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl From<Ipv4Addr> for Host<'_> {
+    fn from(addr: Ipv4Addr) -> Self {
+        Self::Ipv4(addr)
+    }
+}
+
+impl From<Ipv6Addr> for Host<'_> {
+    fn from(addr: Ipv6Addr) -> Self {
+        Self::Ipv6 { addr, zone: None }
+    }
+}
+
+impl From<IpAddr> for Host<'_> {
+    fn from(addr: IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(addr) => Self::Ipv4(addr),
+            IpAddr::V6(addr) => addr.into(),
+        }
+    }
+}
+
+impl fmt::Display for Host<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Ipv4(addr) => write!(f, "{addr}"),
+            Self::Ipv6 { addr, zone: None } => write!(f, "[{addr}]"),
+            Self::Ipv6 {
+                addr,
+                zone: Some(zone),
+            } => write!(f, "[{addr}%25{zone}]"),
+            Self::IpvFuture { version, address } => write!(f, "[v{version}.{address}]"),
+            Self::RegName(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl<'a> Uri<'a> {
+    /// The host parsed into a [`Host`], or `None` if there is no host or it
+    /// is invalid.
+    pub fn typed_host(&self) -> Option<Host<'a>> {
+        Host::parse(self.host?).ok()
+    }
+}
+
+impl UriOwned {
+    /// The host parsed into a [`Host`], or `None` if there is no host or it
+    /// is invalid.
+    pub fn typed_host(&self) -> Option<Host<'_>> {
+        Host::parse(self.host.as_deref()?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hosts() {
+        assert_eq!(
+            Host::parse("192.0.2.16"),
+            Ok(Host::Ipv4(Ipv4Addr::new(192, 0, 2, 16)))
+        );
+        assert_eq!(Host::parse("192.0.2.016"), Ok(Host::RegName("192.0.2.016")));
+        assert_eq!(Host::parse("256.0.0.1"), Ok(Host::RegName("256.0.0.1")));
+        assert_eq!(Host::parse("[::1]"), Ok(Ipv6Addr::LOCALHOST.into()));
+        assert_eq!(
+            Host::parse("[v1F.a:b]"),
+            Ok(Host::IpvFuture {
+                version: "1F",
+                address: "a:b",
+            })
+        );
+        assert_eq!(
+            Host::parse("Ex%41mple.com"),
+            Ok(Host::RegName("Ex%41mple.com"))
+        );
+        assert_eq!(Host::parse(""), Ok(Host::RegName("")));
+        assert_eq!(
+            Host::parse("[::1"),
+            Err(Error::UnterminatedIpLiteral { offset: 0 })
+        );
+        assert!(Host::parse("[::1]:80").is_err());
+        assert!(Host::parse("exa mple").is_err());
+    }
+
+    #[test]
+    fn zone_id() {
+        let host = Host::parse("[FE80::1%25en%2F1]").unwrap();
+        assert_eq!(
+            host,
+            Host::Ipv6 {
+                addr: Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+                zone: Some("en%2F1"),
+            }
+        );
+        assert_eq!(host.to_string(), "[fe80::1%25en%2F1]");
+        assert_eq!(
+            host.ip(),
+            Some(IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)))
+        );
+
+        let uri = UriOwned::new("http://[fe80::1%25eth0]:8080/x").unwrap();
+        assert_eq!(uri.host.as_deref(), Some("[fe80::1%25eth0]"));
+        assert_eq!(uri.typed_host().unwrap().zone(), Some("eth0"));
+        assert_eq!(uri.to_string(), "http://[fe80::1%25eth0]:8080/x");
+    }
+
+    #[test]
+    fn from_uri() {
+        let uri = UriOwned::new("telnet://192.0.2.16:80/").unwrap();
+        assert_eq!(
+            uri.typed_host().and_then(|host| host.ip()),
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 16)))
+        );
+        assert_eq!(Uri::new("mailto:a@b").unwrap().typed_host(), None);
+        assert_eq!(
+            Host::from(IpAddr::V6(Ipv6Addr::LOCALHOST)).to_string(),
+            "[::1]"
+        );
+    }
+}
@@ -1,12 +1,124 @@
-use std::collections::{HashMap, btree_map::Values};
+mod builder;
+mod diagnostic;
+pub mod form_urlencoded;
+mod host;
+mod nested;
+mod normalize;
+mod parse;
+mod percent;
+mod port;
+mod query;
+#[cfg(feature = "serde")]
+mod query_serde;
+mod redact;
+mod resolve;
+mod setters;
+#[cfg(feature = "serde")]
+mod uri_serde;
+mod userinfo;
 
-#[derive(Debug, thiserror::Error)]
+pub use builder::UriBuilder;
+pub use diagnostic::Diagnostic;
+pub use host::Host;
+pub use nested::{ArrayFormat, QueryValue};
+pub use normalize::NormalizedUri;
+pub use percent::{
+    EncodeSet, PercentDecode, PercentDecodeError, percent_decode, percent_decode_cow,
+    percent_decode_iter, percent_decode_lossy, percent_decode_strict, percent_decode_to_bytes,
+    percent_encode, percent_encode_to,
+};
+pub use port::default_port;
+pub use query::{DuplicateKeys, QueryConfig, QueryError, QueryMut, QueryParameters};
+#[cfg(feature = "serde")]
+pub use query_serde::{QuerySerdeError, from_query, from_query_with, to_query};
+pub use redact::{Redacted, SENSITIVE_QUERY_PARAMS};
+#[cfg(feature = "serde")]
+pub use uri_serde::components as serde_components;
+
+/// An error encountered while parsing a URI.
+///
+/// Every variant records the byte offset into the input at which validation
+/// failed; see [`Error::offset`] and [`Error::component`]. Use
+/// [`Error::diagnostic`] to show the failure to a user.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum Error {
-    #[error("URI failed to validate")]
-    Invalid,
+    #[error("empty scheme at byte {offset}")]
+    EmptyScheme { offset: usize },
+    #[error("invalid character {ch:?} in scheme at byte {offset}")]
+    InvalidSchemeChar { offset: usize, ch: char },
+    #[error("invalid character {ch:?} in {component} at byte {offset}")]
+    InvalidChar {
+        component: Component,
+        offset:    usize,
+        ch:        char,
+    },
+    #[error("invalid percent-encoding in {component} at byte {offset}")]
+    InvalidPercentEncoding {
+        component: Component,
+        offset:    usize,
+    },
+    #[error("unterminated IP literal at byte {offset}")]
+    UnterminatedIpLiteral { offset: usize },
+    #[error("invalid IP literal at byte {offset}")]
+    InvalidIpLiteral { offset: usize },
+    #[error("port out of range at byte {offset}")]
+    PortOutOfRange { offset: usize },
+}
+
+impl Error {
+    /// Byte offset into the input at which the error occurred.
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::EmptyScheme { offset }
+            | Self::InvalidSchemeChar { offset, .. }
+            | Self::InvalidChar { offset, .. }
+            | Self::InvalidPercentEncoding { offset, .. }
+            | Self::UnterminatedIpLiteral { offset }
+            | Self::InvalidIpLiteral { offset }
+            | Self::PortOutOfRange { offset } => offset,
+        }
+    }
+
+    /// The URI component that failed to validate.
+    pub fn component(&self) -> Component {
+        match *self {
+            Self::EmptyScheme { .. } | Self::InvalidSchemeChar { .. } => Component::Scheme,
+            Self::InvalidChar { component, .. }
+            | Self::InvalidPercentEncoding { component, .. } => component,
+            Self::UnterminatedIpLiteral { .. } | Self::InvalidIpLiteral { .. } => Component::Host,
+            Self::PortOutOfRange { .. } => Component::Port,
+        }
+    }
+}
+
+/// A component of a URI, as named by [RFC 3986 section 3].
+///
+/// [RFC 3986 section 3]: https://datatracker.ietf.org/doc/html/rfc3986#section-3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+    Scheme,
+    Userinfo,
+    Host,
+    Port,
+    Path,
+    Query,
+    Fragment,
+}
+
+impl std::fmt::Display for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str(match self {
+            Self::Scheme => "scheme",
+            Self::Userinfo => "userinfo",
+            Self::Host => "host",
+            Self::Port => "port",
+            Self::Path => "path",
+            Self::Query => "query",
+            Self::Fragment => "fragment",
+        })
+    }
 }
 
-pub type QueryParameters = HashMap<String, Option<String>>;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Uri<'a> {
     pub scheme:   Option<&'a str>,
@@ -19,85 +131,34 @@ pub struct Uri<'a> {
 }
 
 impl<'a> Uri<'a> {
-    pub fn new(mut src: &'a str) -> Result<Self, Error> {
-        let mut uri = Uri {
-            scheme:   None,
-            userinfo: None,
-            host:     None,
-            port:     None,
-            path:     None,
-            query:    None,
-            fragment: None,
-        };
-
-        if let Some((rest, frag)) = src.split_once('#') {
-            src = rest;
-            uri.fragment = Some(frag);
-        }
-        if let Some((rest, query)) = src.split_once('?') {
-            src = rest;
-            uri.query = Some(query);
-        }
-
-        if src.starts_with(char::is_alphabetic) {
-            if let Some((scheme, rest)) = src.split_once(':') {
-                if scheme.chars().all(is_scheme) {
-                    uri.scheme = Some(scheme);
-                    src = rest;
-                }
-            }
-        }
-
-        if let Some(rest) = src.strip_prefix("//") {
-            src = rest;
-            if let Some((rest, path)) = rest.split_once('/') {
-                uri.path = Some(path);
-                src = rest;
-            }
-
-            if let Some((rest, port)) = src.rsplit_once(':') {
-                if port.chars().all(|x| x.is_ascii_digit()) {
-                    uri.port = Some(port);
-                    src = rest;
-                }
-            }
-            if let Some((userinfo, host)) = src.split_once('@') {
-                uri.userinfo = Some(userinfo);
-                uri.host = Some(host);
-            } else {
-                uri.host = Some(src);
-            }
-        } else {
-            uri.path = Some(src);
-        }
-
-        Ok(uri)
+    /// Parse and validate a URI-reference as defined by [RFC 3986].
+    ///
+    /// Returns an [`Error`] pointing at the first byte that does not match the
+    /// RFC 3986 grammar.
+    ///
+    /// [RFC 3986]: https://datatracker.ietf.org/doc/html/rfc3986#section-4.1
+    pub fn new(src: &'a str) -> Result<Self, Error> {
+        parse::parse(src)
     }
 
     /// Get query parameters
-    pub fn get_query_parameters(&self) -> Option<QueryParameters> {
-        let mut map = HashMap::new();
-        for param in self.query?.split('&') {
-            match param.split_once('=') {
-                Some((key, value)) => {
-                    let Some(key) = percent_decode(key) else {
-                        continue;
-                    };
-                    let Some(value) = percent_decode(value) else {
-                        continue;
-                    };
-                    map.insert(key, Some(value));
-                }
-                None => {
-                    let Some(key) = percent_decode(param) else {
-                        continue;
-                    };
-                    map.insert(key, None);
-                }
-            }
-        }
+    pub fn get_query_parameters(&self) -> Option<QueryParameters<'a>> {
+        Some(QueryParameters::parse(self.query?))
+    }
 
-        Some(map)
+    /// Get query parameters, parsing the query as described by `config`.
+    pub fn get_query_parameters_with(
+        &self,
+        config: &QueryConfig,
+    ) -> Option<Result<QueryParameters<'a>, QueryError>> {
+        Some(config.parse(self.query?))
+    }
+
+    /// Get query parameters, parsing the query as
+    /// [`application/x-www-form-urlencoded`](form_urlencoded) so that `+`
+    /// decodes to a space.
+    pub fn get_form_parameters(&self) -> Option<QueryParameters<'a>> {
+        Some(form_urlencoded::parse(self.query?))
     }
 }
 impl<'a> TryFrom<&'a str> for Uri<'a> {
@@ -142,7 +203,7 @@ impl std::fmt::Display for Uri<'_> {
             }
             if let Some(path) = self.path {
                 write!(f, "/")?;
-                write!(f, "{}", path.trim_start_matches("/"))?;
+                write!(f, "{path}")?;
             }
         } else if let Some(path) = self.path {
             write!(f, "{path}")?;
@@ -159,7 +220,7 @@ impl std::fmt::Display for Uri<'_> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct UriOwned {
     pub scheme:   Option<String>,
     pub userinfo: Option<String>,
@@ -188,7 +249,7 @@ impl UriOwned {
     pub fn new(s: &str) -> Result<Self, Error> {
         Ok(Uri::new(s)?.into())
     }
-    pub fn as_ref(&self) -> Uri {
+    pub fn as_ref(&self) -> Uri<'_> {
         self.into()
     }
 }
@@ -200,33 +261,6 @@ impl std::fmt::Display for UriOwned {
     }
 }
 
-fn is_scheme(c: char) -> bool {
-    c.is_alphabetic() || c.is_ascii_digit() || "+-.".contains(c)
-}
-
-pub fn percent_decode(s: impl AsRef<str>) -> Option<String> {
-    let s = s.as_ref();
-    let mut out = String::new();
-    let mut rem = 0;
-    for (i, ch) in s.chars().enumerate() {
-        if rem == 0 {
-            if ch == '%' {
-                rem = 2;
-            } else {
-                out.push(ch);
-            }
-            continue;
-        }
-        rem -= 1;
-        if rem == 0 {
-            out.push(u8::from_str_radix(&s[i - 1..=i], 16).ok().map(char::from)?);
-        }
-    }
-    Some(out)
-}
-
-// TODO: Percent Encode
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,12 +309,12 @@ mod tests {
         let uri = Uri::new(test10).unwrap();
         assert_eq!(
             uri.get_query_parameters().unwrap(),
-            HashMap::from([("v".to_string(), Some("QyjyWUrHsFc".to_string()))])
+            QueryParameters::from_iter([("v", Some("QyjyWUrHsFc"))])
         );
         let uri = Uri::new(test11).unwrap();
         assert_eq!(
             uri.get_query_parameters().unwrap(),
-            HashMap::from([("query".to_string(), None)])
+            QueryParameters::from_iter([("query", None::<&str>)])
         );
         assert_eq!(uri.scheme, Some("https"));
         assert_eq!(uri.userinfo, Some("john.doe"));
@@ -301,6 +335,7 @@ mod tests {
         let test6 = "tel:+1-816-555-1212";
         let test7 = "telnet://192.0.2.16:80/";
         let test8 = "urn:oasis:names:specification:docbook:dtd:xml:4.1.2";
+        let test9 = "http://example.com//double/slash";
 
         let uri1 = Uri::new(test1).unwrap();
         assert_eq!(UriOwned::from(dbg!(uri1)).to_string(), test1);
@@ -318,5 +353,7 @@ mod tests {
         assert_eq!(UriOwned::from(dbg!(uri7)).to_string(), test7);
         let uri8 = Uri::new(test8).unwrap();
         assert_eq!(UriOwned::from(dbg!(uri8)).to_string(), test8);
+        let uri9 = Uri::new(test9).unwrap();
+        assert_eq!(UriOwned::from(dbg!(uri9)).to_string(), test9);
     }
 }
@@ -0,0 +1,508 @@
+//! Nested query strings in the bracket style of PHP, Rails and `qs`, where
+//! `a[]=1&a[]=2` is an array and `filter[status]=open` is a map.
+
+use crate::{
+    EncodeSet, QueryConfig, QueryError, percent_encode, percent_encode_to, query::split_pairs,
+};
+
+/// Bracket groups past this depth are kept as part of a literal key, which
+/// bounds recursion on hostile input.
+const MAX_DEPTH: usize = 16;
+
+/// How [`QueryConfig::serialize_nested`] writes an array, here `a = [1, 2]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ArrayFormat {
+    /// `a[]=1&a[]=2`
+    Brackets,
+    /// `a[0]=1&a[1]=2`
+    #[default]
+    Indices,
+    /// `a=1&a=2`
+    Repeat,
+    /// `a=1,2`. Values are also split on unencoded commas when parsing.
+    ///
+    /// Arrays holding maps or arrays fall back to [`ArrayFormat::Indices`].
+    Comma,
+}
+
+/// A value in a nested query: a string, or an array or map of values.
+///
+/// Maps keep their keys in the order they first appear in the query.
+///
+/// ```
+/// use uri_rs::QueryValue;
+///
+/// let query = QueryValue::parse("a[]=1&a[]=2&filter[status]=open&b[0][c]=x").unwrap();
+/// let a = query.get("a").and_then(QueryValue::as_array).unwrap();
+/// assert_eq!(a, [QueryValue::from("1"), QueryValue::from("2")]);
+/// assert_eq!(
+///     query.get("filter").and_then(|f| f.get("status")),
+///     Some(&QueryValue::from("open"))
+/// );
+/// assert_eq!(
+///     query
+///         .get("b")
+///         .and_then(|b| b.get("0"))
+///         .and_then(|b| b.get("c"))
+///         .and_then(QueryValue::as_str),
+///     Some("x")
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum QueryValue {
+    String(String),
+    Array(Vec<QueryValue>),
+    Map(Vec<(String, QueryValue)>),
+}
+
+impl QueryValue {
+    /// Parse `query` into a map with [`QueryConfig::parse_nested`] and the
+    /// default configuration.
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        QueryConfig::new().parse_nested(query)
+    }
+
+    /// The value of `key` in a map, or at index `key` in an array.
+    pub fn get(&self, key: &str) -> Option<&QueryValue> {
+        match self {
+            Self::String(_) => None,
+            Self::Array(items) => items.get(key.parse::<usize>().ok()?),
+            Self::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[QueryValue]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&[(String, QueryValue)]> {
+        match self {
+            Self::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for QueryValue {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<String> for QueryValue {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl QueryConfig {
+    /// Parse `query` into a [`QueryValue::Map`], splitting bracketed keys
+    /// into nested maps and arrays.
+    ///
+    /// - `a[]=1&a[]=2`, `a[0]=1&a[1]=2` and `a=1&a=2` are all the array
+    ///   `[1, 2]`; indices only decide the order.
+    /// - `a[b]=1` is the map `{b: 1}`.
+    /// - With [`ArrayFormat::Comma`], `a=1,2` is the array `[1, 2]`.
+    ///
+    /// Repeated keys always build arrays; the
+    /// [duplicate-key policy](Self::duplicates) doesn't apply.
+    ///
+    /// ```
+    /// use uri_rs::{ArrayFormat, QueryConfig, QueryValue};
+    ///
+    /// let config = QueryConfig::new().array_format(ArrayFormat::Comma);
+    /// let query = config.parse_nested("ids=1,2,3&name=a%2Cb").unwrap();
+    /// assert_eq!(query.get("ids").and_then(QueryValue::as_array).unwrap().len(), 3);
+    /// assert_eq!(query.get("name").and_then(QueryValue::as_str), Some("a,b"));
+    /// ```
+    pub fn parse_nested(&self, query: &str) -> Result<QueryValue, QueryError> {
+        let mut root = Vec::new();
+        for (offset, key, value) in split_pairs(query, &self.separators, self.delimiter) {
+            let value_offset = offset + key.len() + self.delimiter.len_utf8();
+            let key = self.decode(key, offset, offset)?;
+            let Some(value) = value else {
+                insert(&mut root, &split_key(&key), String::new());
+                continue;
+            };
+            let mut path = split_key(&key);
+            if self.array_format != ArrayFormat::Comma || !value.contains(',') {
+                let value = self.decode(value, offset, value_offset)?;
+                insert(&mut root, &path, value.into_owned());
+                continue;
+            }
+            if path.last() != Some(&"") {
+                path.push("");
+            }
+            let mut at = value_offset;
+            for item in value.split(',') {
+                let decoded = self.decode(item, offset, at)?;
+                at += item.len() + 1;
+                insert(&mut root, &path, decoded.into_owned());
+            }
+        }
+        Ok(finish(root, true))
+    }
+
+    /// Serialize `value` into a query, writing nested keys with brackets and
+    /// arrays as chosen by [`array_format`](Self::array_format).
+    ///
+    /// Pairs are joined with the first of the [separators](Self::separators).
+    /// A single-item array written with [`ArrayFormat::Repeat`] or
+    /// [`ArrayFormat::Comma`] reads back as a plain string.
+    ///
+    /// ```
+    /// use uri_rs::{ArrayFormat, QueryConfig, QueryValue};
+    ///
+    /// let query = QueryValue::parse("a[]=1&a[]=2&f[s]=x").unwrap();
+    /// let config = QueryConfig::new();
+    /// assert_eq!(
+    ///     config.serialize_nested(&query),
+    ///     "a%5B0%5D=1&a%5B1%5D=2&f%5Bs%5D=x"
+    /// );
+    /// let config = config.array_format(ArrayFormat::Repeat);
+    /// assert_eq!(config.serialize_nested(&query), "a=1&a=2&f%5Bs%5D=x");
+    /// ```
+    pub fn serialize_nested(&self, value: &QueryValue) -> String {
+        let mut set = EncodeSet::QUERY_PARAM;
+        for &ch in self.separators.iter().chain([&self.delimiter]) {
+            if ch.is_ascii() {
+                set = set.add(ch as u8);
+            }
+        }
+        let mut pairs = Vec::new();
+        match value {
+            QueryValue::String(s) => pairs.push((s.clone(), None)),
+            QueryValue::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    self.flatten(i.to_string(), item, &set, &mut pairs);
+                }
+            }
+            QueryValue::Map(entries) => {
+                for (key, value) in entries {
+                    self.flatten(key.clone(), value, &set, &mut pairs);
+                }
+            }
+        }
+
+        let separator = self.separators.first().copied().unwrap_or('&');
+        let mut query = String::new();
+        for (i, (key, value)) in pairs.iter().enumerate() {
+            if i > 0 {
+                query.push(separator);
+            }
+            percent_encode_to(key, &set, &mut query);
+            if let Some(value) = value {
+                query.push(self.delimiter);
+                query.push_str(value);
+            }
+        }
+        query
+    }
+
+    /// Push the unencoded key and encoded value of every string in `value`.
+    fn flatten(
+        &self,
+        key: String,
+        value: &QueryValue,
+        set: &EncodeSet,
+        pairs: &mut Vec<(String, Option<String>)>,
+    ) {
+        let items = match value {
+            QueryValue::String(s) => return pairs.push((key, Some(percent_encode(s, set)))),
+            QueryValue::Map(entries) => {
+                for (k, v) in entries {
+                    self.flatten(format!("{key}[{k}]"), v, set, pairs);
+                }
+                return;
+            }
+            QueryValue::Array(items) => items,
+        };
+        let strings: Option<Vec<_>> = items.iter().map(QueryValue::as_str).collect();
+        match (self.array_format, strings) {
+            (ArrayFormat::Comma, Some(strings)) if !strings.is_empty() => {
+                let set = set.add(b',');
+                let values: Vec<_> = strings.iter().map(|s| percent_encode(s, &set)).collect();
+                pairs.push((key, Some(values.join(","))));
+            }
+            (ArrayFormat::Brackets, _) => {
+                for item in items {
+                    self.flatten(format!("{key}[]"), item, set, pairs);
+                }
+            }
+            (ArrayFormat::Repeat, _) => {
+                for item in items {
+                    self.flatten(key.clone(), item, set, pairs);
+                }
+            }
+            _ => {
+                for (i, item) in items.iter().enumerate() {
+                    self.flatten(format!("{key}[{i}]"), item, set, pairs);
+                }
+            }
+        }
+    }
+}
+
+/// Split `a[b][]` into `["a", "b", ""]`.
+///
+/// A key that doesn't start with a name followed by a bracket group is a
+/// single segment, and anything after the last group, or past
+/// [`MAX_DEPTH`], is kept as a final literal segment.
+fn split_key(key: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut rest = match key.find('[') {
+        Some(open) if open > 0 => {
+            segments.push(&key[..open]);
+            &key[open..]
+        }
+        _ => return vec![key],
+    };
+    while segments.len() <= MAX_DEPTH {
+        let Some((segment, after)) = rest
+            .strip_prefix('[')
+            .and_then(|inner| inner.split_once(']'))
+        else {
+            break;
+        };
+        segments.push(segment);
+        rest = after;
+    }
+    if segments.len() == 1 {
+        return vec![key];
+    }
+    if !rest.is_empty() {
+        segments.push(rest);
+    }
+    segments
+}
+
+/// A value under construction. Keys are empty for appended array items.
+enum Node {
+    Leaf(String),
+    Branch(Vec<(String, Node)>),
+}
+
+impl Node {
+    /// The children of this node, turning a leaf into an array holding it.
+    fn children(&mut self) -> &mut Vec<(String, Node)> {
+        if let Node::Leaf(value) = self {
+            let value = std::mem::take(value);
+            *self = Node::Branch(vec![(String::new(), Node::Leaf(value))]);
+        }
+        match self {
+            Node::Branch(children) => children,
+            Node::Leaf(_) => unreachable!("leaves were just converted"),
+        }
+    }
+}
+
+fn insert(entries: &mut Vec<(String, Node)>, path: &[&str], value: String) {
+    let (key, rest) = path.split_first().expect("keys have at least one segment");
+    // An empty segment, as in `a[]`, always appends.
+    let existing = (!key.is_empty())
+        .then(|| entries.iter().position(|(k, _)| k == key))
+        .flatten();
+    let Some(i) = existing else {
+        let node = if rest.is_empty() {
+            Node::Leaf(value)
+        } else {
+            let mut children = Vec::new();
+            insert(&mut children, rest, value);
+            Node::Branch(children)
+        };
+        entries.push((key.to_string(), node));
+        return;
+    };
+    let children = entries[i].1.children();
+    if rest.is_empty() {
+        children.push((String::new(), Node::Leaf(value)));
+    } else {
+        insert(children, rest, value);
+    }
+}
+
+/// Turn a branch whose keys are all indices or appends into an array and any
+/// other branch into a map.
+fn finish(entries: Vec<(String, Node)>, root: bool) -> QueryValue {
+    let finish_node = |node| match node {
+        Node::Leaf(value) => QueryValue::String(value),
+        Node::Branch(entries) => finish(entries, false),
+    };
+    let is_index = |k: &str| !k.is_empty() && k.bytes().all(|b| b.is_ascii_digit());
+    if !root && entries.iter().all(|(k, _)| k.is_empty() || is_index(k)) {
+        let mut items: Vec<_> = entries
+            .into_iter()
+            .map(|(k, node)| (k.parse::<usize>().unwrap_or(usize::MAX), node))
+            .collect();
+        // Stable, so appended items keep their order after indexed ones.
+        items.sort_by_key(|(i, _)| *i);
+        return QueryValue::Array(
+            items
+                .into_iter()
+                .map(|(_, node)| finish_node(node))
+                .collect(),
+        );
+    }
+    QueryValue::Map(
+        entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, (k, node))| {
+                let key = if k.is_empty() { i.to_string() } else { k };
+                (key, finish_node(node))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PercentDecodeError;
+
+    fn map<const N: usize>(entries: [(&str, QueryValue); N]) -> QueryValue {
+        QueryValue::Map(entries.map(|(k, v)| (k.to_string(), v)).to_vec())
+    }
+
+    fn array<const N: usize>(items: [&str; N]) -> QueryValue {
+        QueryValue::Array(items.map(QueryValue::from).to_vec())
+    }
+
+    #[test]
+    fn parse_nested() {
+        let expected = map([("a", array(["1", "2"]))]);
+        for query in [
+            "a[]=1&a[]=2",
+            "a[1]=2&a[0]=1",
+            "a=1&a=2",
+            "a%5B%5D=1&a%5B%5D=2",
+        ] {
+            assert_eq!(QueryValue::parse(query).unwrap(), expected, "{query:?}");
+        }
+
+        assert_eq!(
+            QueryValue::parse("filter[status]=open&filter[tags][]=x&a[0][b]=x&a[0][c]=y&a[1][b]=z")
+                .unwrap(),
+            map([
+                (
+                    "filter",
+                    map([("status", "open".into()), ("tags", array(["x"]))])
+                ),
+                (
+                    "a",
+                    QueryValue::Array(vec![
+                        map([("b", "x".into()), ("c", "y".into())]),
+                        map([("b", "z".into())]),
+                    ])
+                ),
+            ])
+        );
+
+        assert_eq!(
+            QueryValue::parse("flag&a[b]=1&a=2&[x]=1&c[d=1&e[f]g=1&0=z").unwrap(),
+            map([
+                ("flag", "".into()),
+                ("a", map([("b", "1".into()), ("1", "2".into())])),
+                ("[x]", "1".into()),
+                ("c[d", "1".into()),
+                ("e", map([("f", map([("g", "1".into())]))])),
+                ("0", "z".into()),
+            ])
+        );
+        assert_eq!(QueryValue::parse("").unwrap(), map([]));
+
+        let deep = format!("a{}=1", "[b]".repeat(100));
+        let mut value = &QueryValue::parse(&deep).unwrap();
+        let mut depth = 0;
+        while let QueryValue::Map(entries) = value {
+            value = &entries[0].1;
+            depth += 1;
+        }
+        assert_eq!(depth, MAX_DEPTH + 2);
+    }
+
+    #[test]
+    fn parse_comma() {
+        let config = QueryConfig::new().array_format(ArrayFormat::Comma);
+        assert_eq!(
+            config.parse_nested("a=1,2&a=3&b=x%2Cy&c[]=4,5").unwrap(),
+            map([
+                ("a", array(["1", "2", "3"])),
+                ("b", "x,y".into()),
+                ("c", array(["4", "5"])),
+            ])
+        );
+        assert_eq!(
+            config.parse_nested("k=v&a=1,%zz"),
+            Err(QueryError::Malformed {
+                offset: 4,
+                error:  PercentDecodeError::InvalidEscape { offset: 8 },
+            })
+        );
+        assert_eq!(
+            QueryValue::parse("a=1,2").unwrap(),
+            map([("a", "1,2".into())])
+        );
+    }
+
+    #[test]
+    fn serialize_nested() {
+        let value = map([
+            ("a", array(["1", "x,y"])),
+            ("f", map([("s", "a b".into()), ("t", array([]))])),
+            ("n", QueryValue::Array(vec![map([("b", "x".into())])])),
+        ]);
+        for (format, expected) in [
+            (
+                ArrayFormat::Indices,
+                "a%5B0%5D=1&a%5B1%5D=x,y&f%5Bs%5D=a%20b&n%5B0%5D%5Bb%5D=x",
+            ),
+            (
+                ArrayFormat::Brackets,
+                "a%5B%5D=1&a%5B%5D=x,y&f%5Bs%5D=a%20b&n%5B%5D%5Bb%5D=x",
+            ),
+            (ArrayFormat::Repeat, "a=1&a=x,y&f%5Bs%5D=a%20b&n%5Bb%5D=x"),
+            (
+                ArrayFormat::Comma,
+                "a=1,x%2Cy&f%5Bs%5D=a%20b&n%5B0%5D%5Bb%5D=x",
+            ),
+        ] {
+            let config = QueryConfig::new().array_format(format);
+            let query = config.serialize_nested(&value);
+            assert_eq!(query, expected, "{format:?}");
+            if format != ArrayFormat::Repeat {
+                // Empty arrays are left out.
+                let mut round_trip = value.clone();
+                let QueryValue::Map(entries) = &mut round_trip else {
+                    unreachable!()
+                };
+                entries[1].1 = map([("s", "a b".into())]);
+                assert_eq!(
+                    config.parse_nested(&query).unwrap(),
+                    round_trip,
+                    "{format:?}"
+                );
+            }
+        }
+
+        let legacy = QueryConfig::new().separators(&[';']).delimiter(':');
+        let value = map([("a;b", array(["c:d", "e"]))]);
+        let query = legacy.serialize_nested(&value);
+        assert_eq!(query, "a%3Bb%5B0%5D:c%3Ad;a%3Bb%5B1%5D:e");
+        assert_eq!(legacy.parse_nested(&query).unwrap(), value);
+        assert_eq!(
+            QueryConfig::new().serialize_nested(&array(["x", "y"])),
+            "0=x&1=y"
+        );
+    }
+}
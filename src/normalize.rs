@@ -0,0 +1,312 @@
+//! Syntax- and scheme-based normalization as described in
+//! [RFC 3986 section 6.2].
+//!
+//! [RFC 3986 section 6.2]: https://datatracker.ietf.org/doc/html/rfc3986#section-6.2
+
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    Uri, UriOwned,
+    parse::is_unreserved,
+    percent::decode_escape,
+    port::default_port,
+    resolve::{Authority, remove_dot_segments},
+};
+
+impl Uri<'_> {
+    /// Normalize this URI so that equivalent URIs compare equal.
+    ///
+    /// - The scheme and host are lowercased.
+    /// - Percent-encoded unreserved characters are decoded and the hex digits
+    ///   of the remaining escapes are uppercased.
+    /// - `.` and `..` segments are removed from an absolute path.
+    /// - An empty port, or the default port of a known scheme, is removed.
+    /// - An empty `http` or `https` path becomes `/`.
+    ///
+    /// ```
+    /// use uri_rs::Uri;
+    ///
+    /// let uri = Uri::new("HTTP://Example.COM:80/a/./b/../%7e%2f").unwrap();
+    /// assert_eq!(uri.normalize().to_string(), "http://example.com/a/~%2F");
+    /// ```
+    pub fn normalize(&self) -> UriOwned {
+        let scheme = self.scheme.map(str::to_ascii_lowercase);
+        let host = self.host.map(|host| match host.split_once("%25") {
+            // Zone identifiers are case-sensitive.
+            Some((addr, zone)) if host.starts_with('[') => {
+                format!(
+                    "{}%25{}",
+                    addr.to_ascii_lowercase(),
+                    normalize_escapes(zone, false)
+                )
+            }
+            _ => normalize_escapes(host, true),
+        });
+        let port = self.port.and_then(|port| {
+            let Ok(n) = port.parse::<u16>() else {
+                return (!port.is_empty()).then(|| port.to_string());
+            };
+            let default = scheme.as_deref().and_then(default_port);
+            (default != Some(n)).then(|| n.to_string())
+        });
+
+        let mut path = normalize_escapes(&self.full_path(), false);
+        // Removing dot segments from a rootless path can make it absolute.
+        if path.starts_with('/') {
+            path = remove_dot_segments(&path);
+        }
+        if path.is_empty() && host.is_some() && matches!(scheme.as_deref(), Some("http" | "https"))
+        {
+            path.push('/');
+        }
+
+        let userinfo = self.userinfo.map(|s| normalize_escapes(s, false));
+        let query = self.query.map(|s| normalize_escapes(s, false));
+        let fragment = self.fragment.map(|s| normalize_escapes(s, false));
+        let authority = host.as_deref().map(|host| Authority {
+            userinfo: userinfo.as_deref(),
+            host,
+            port: port.as_deref(),
+        });
+        UriOwned::from_parts(
+            scheme.as_deref(),
+            authority,
+            path,
+            query.as_deref(),
+            fragment.as_deref(),
+        )
+    }
+
+    /// Whether `self` and `other` are equivalent once both are
+    /// [normalized](Self::normalize).
+    ///
+    /// ```
+    /// use uri_rs::Uri;
+    ///
+    /// let a = Uri::new("HTTP://Example.com:80/%7e").unwrap();
+    /// let b = Uri::new("http://example.com/~").unwrap();
+    /// assert_ne!(a, b);
+    /// assert!(a.eq_normalized(&b));
+    /// ```
+    pub fn eq_normalized(&self, other: &Uri) -> bool {
+        self.normalize() == other.normalize()
+    }
+}
+
+impl UriOwned {
+    /// Normalize this URI so that equivalent URIs compare equal.
+    ///
+    /// See [`Uri::normalize`].
+    pub fn normalize(&self) -> UriOwned {
+        self.as_ref().normalize()
+    }
+
+    /// Whether `self` and `other` are equivalent once both are normalized.
+    ///
+    /// See [`Uri::eq_normalized`].
+    pub fn eq_normalized(&self, other: &UriOwned) -> bool {
+        self.as_ref().eq_normalized(&other.as_ref())
+    }
+}
+
+/// A URI that compares, hashes and orders by its [normalized](Uri::normalize)
+/// form, for use as a map key.
+///
+/// The URI it was created from is kept and is what [`Display`] prints.
+///
+/// ```
+/// use std::collections::HashSet;
+///
+/// use uri_rs::{NormalizedUri, UriOwned};
+///
+/// let mut seen = HashSet::new();
+/// seen.insert(NormalizedUri::new(UriOwned::new("HTTP://Example.com:80/%7e").unwrap()));
+/// assert!(seen.contains(&NormalizedUri::new(UriOwned::new("http://example.com/~").unwrap())));
+/// ```
+///
+/// [`Display`]: std::fmt::Display
+#[derive(Debug, Clone)]
+pub struct NormalizedUri {
+    original:   UriOwned,
+    normalized: UriOwned,
+}
+
+impl NormalizedUri {
+    pub fn new(uri: UriOwned) -> Self {
+        Self {
+            normalized: uri.normalize(),
+            original:   uri,
+        }
+    }
+
+    /// Like [`NormalizedUri::new`], but the fragment is left out of
+    /// comparisons.
+    pub fn ignoring_fragment(uri: UriOwned) -> Self {
+        let mut normalized = uri.normalize();
+        normalized.fragment = None;
+        Self {
+            original: uri,
+            normalized,
+        }
+    }
+
+    /// The URI this was created from.
+    pub fn original(&self) -> &UriOwned {
+        &self.original
+    }
+
+    /// The normalized form used for comparisons.
+    pub fn normalized(&self) -> &UriOwned {
+        &self.normalized
+    }
+
+    pub fn into_inner(self) -> UriOwned {
+        self.original
+    }
+}
+
+impl From<UriOwned> for NormalizedUri {
+    fn from(uri: UriOwned) -> Self {
+        Self::new(uri)
+    }
+}
+
+impl From<Uri<'_>> for NormalizedUri {
+    fn from(uri: Uri) -> Self {
+        Self::new(uri.into())
+    }
+}
+
+impl PartialEq for NormalizedUri {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized == other.normalized
+    }
+}
+
+impl Eq for NormalizedUri {}
+
+impl Hash for NormalizedUri {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized.hash(state);
+    }
+}
+
+impl PartialOrd for NormalizedUri {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NormalizedUri {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.normalized.cmp(&other.normalized)
+    }
+}
+
+impl std::fmt::Display for NormalizedUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.original)
+    }
+}
+
+/// Decode escaped unreserved characters and uppercase the hex digits of the
+/// remaining escapes, optionally lowercasing everything else.
+fn normalize_escapes(s: &str, lowercase: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.char_indices();
+    while let Some((i, ch)) = chars.next() {
+        let escape = (ch == '%')
+            .then(|| decode_escape(&s.as_bytes()[i + 1..]))
+            .flatten();
+        match escape {
+            Some(b) if is_unreserved(b) => {
+                out.push(char::from(if lowercase {
+                    b.to_ascii_lowercase()
+                } else {
+                    b
+                }));
+                chars.nth(1);
+            }
+            Some(_) => {
+                out.push('%');
+                out.push_str(&s[i + 1..i + 3].to_ascii_uppercase());
+                chars.nth(1);
+            }
+            None if lowercase => out.push(ch.to_ascii_lowercase()),
+            None => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Uri;
+
+    fn normalize(s: &str) -> String {
+        Uri::new(s).unwrap().normalize().to_string()
+    }
+
+    #[test]
+    fn normalize_uris() {
+        for (input, expected) in [
+            ("HTTP://Example.com:80/%7e", "http://example.com/~"),
+            ("http://example.com", "http://example.com/"),
+            ("https://example.com:443", "https://example.com/"),
+            ("https://example.com:0443/", "https://example.com/"),
+            ("http://example.com:/", "http://example.com/"),
+            ("http://example.com:8080/", "http://example.com:8080/"),
+            ("ftp://example.com:80/", "ftp://example.com:80/"),
+            ("foo://example.com", "foo://example.com"),
+            ("http://%45X%61mple.com/", "http://example.com/"),
+            ("http://ex%c3%a4mple.com/", "http://ex%C3%A4mple.com/"),
+            ("http://[2001:DB8::7]/", "http://[2001:db8::7]/"),
+            ("http://[FE80::1%25Eth%2d0]/", "http://[fe80::1%25Eth-0]/"),
+            ("http://User%3a@example.com/", "http://User%3A@example.com/"),
+            ("http://a/b/c/./../../g", "http://a/g"),
+            ("http://a/%2E%2E/%2e/b", "http://a/b"),
+            ("http://a/b?%7E%2f#%2d%3F", "http://a/b?~%2F#-%3F"),
+            ("mailto:Joe@Example.COM", "mailto:Joe@Example.COM"),
+            ("A:./b/../c", "a:./b/../c"),
+            ("a:/b/..//c", "a:/.//c"),
+        ] {
+            assert_eq!(normalize(input), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn normalized_keys() {
+        use std::collections::HashMap;
+
+        use crate::{NormalizedUri, UriOwned};
+
+        let key = |s| NormalizedUri::new(UriOwned::new(s).unwrap());
+        let mut map = HashMap::new();
+        map.insert(key("HTTP://Example.com:80/%7e"), 1);
+        *map.entry(key("http://example.com/~")).or_default() += 1;
+        *map.entry(key("http://example.com/~#top")).or_default() += 1;
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&key("http://EXAMPLE.com/%7E")], 2);
+
+        let a = NormalizedUri::ignoring_fragment(UriOwned::new("http://a/b#x").unwrap());
+        let b = NormalizedUri::ignoring_fragment(UriOwned::new("http://A/b#y").unwrap());
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), "http://a/b#x");
+        assert!(key("http://a/b") < key("http://a/c"));
+    }
+
+    #[test]
+    fn idempotent() {
+        for input in [
+            "HTTP://Example.com:80/%7e",
+            "http://a/%2E%2E/%2e/b",
+            "a:/b/..//c",
+        ] {
+            let once = normalize(input);
+            assert_eq!(normalize(&once), once, "{input:?}");
+        }
+    }
+}
@@ -0,0 +1,451 @@
+//! Strict RFC 3986 URI-reference parser.
+//!
+//! Every component is checked against the ABNF in [RFC 3986 Appendix A]; a
+//! string only parses if it is a valid `URI-reference`. Errors report the byte
+//! offset into the original input at which validation failed.
+//!
+//! [RFC 3986 Appendix A]: https://datatracker.ietf.org/doc/html/rfc3986#appendix-A
+
+use std::net::Ipv4Addr;
+
+use crate::{Component, Error, Host, Uri};
+
+pub(crate) fn parse(src: &str) -> Result<Uri<'_>, Error> {
+    let mut uri = Uri {
+        scheme:   None,
+        userinfo: None,
+        host:     None,
+        port:     None,
+        path:     None,
+        query:    None,
+        fragment: None,
+    };
+    let mut rest = src;
+
+    if let Some(i) = rest.find('#') {
+        let fragment = &rest[i + 1..];
+        validate(fragment, i + 1, Component::Fragment, is_query_char)?;
+        uri.fragment = Some(fragment);
+        rest = &rest[..i];
+    }
+    if let Some(i) = rest.find('?') {
+        let query = &rest[i + 1..];
+        validate(query, i + 1, Component::Query, is_query_char)?;
+        uri.query = Some(query);
+        rest = &rest[..i];
+    }
+
+    // A ':' before the first '/' can only end a scheme: a relative reference
+    // may not contain one in its first path segment.
+    let mut offset = 0;
+    if let Some(i) = rest.find([':', '/'])
+        && rest.as_bytes()[i] == b':'
+    {
+        let scheme = &rest[..i];
+        validate_scheme(scheme)?;
+        uri.scheme = Some(scheme);
+        rest = &rest[i + 1..];
+        offset = i + 1;
+    }
+
+    if let Some(authority) = rest.strip_prefix("//") {
+        offset += 2;
+        let authority = match authority.split_once('/') {
+            Some((authority, path)) => {
+                let path_offset = offset + authority.len() + 1;
+                validate(path, path_offset, Component::Path, is_path_char)?;
+                uri.path = Some(path);
+                authority
+            }
+            None => authority,
+        };
+        parse_authority(authority, offset, &mut uri)?;
+    } else {
+        validate(rest, offset, Component::Path, is_path_char)?;
+        uri.path = Some(rest);
+    }
+
+    Ok(uri)
+}
+
+fn parse_authority<'a>(
+    mut authority: &'a str,
+    mut offset: usize,
+    uri: &mut Uri<'a>,
+) -> Result<(), Error> {
+    if let Some((userinfo, rest)) = authority.split_once('@') {
+        validate(userinfo, offset, Component::Userinfo, is_userinfo_char)?;
+        uri.userinfo = Some(userinfo);
+        authority = rest;
+        offset += userinfo.len() + 1;
+    }
+
+    let (host, port) = if authority.starts_with('[') {
+        let end = authority
+            .find(']')
+            .ok_or(Error::UnterminatedIpLiteral { offset })?;
+        let (host, rest) = authority.split_at(end + 1);
+        let port = match rest.strip_prefix(':') {
+            Some(port) => Some(port),
+            None => match rest.chars().next() {
+                Some(ch) => {
+                    return Err(Error::InvalidChar {
+                        component: Component::Host,
+                        offset: offset + host.len(),
+                        ch,
+                    });
+                }
+                None => None,
+            },
+        };
+        (host, port)
+    } else {
+        match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    parse_host(host, offset)?;
+    uri.host = Some(host);
+
+    if let Some(port) = port {
+        validate_port(port, offset + authority.len() - port.len())?;
+        uri.port = Some(port);
+    }
+    Ok(())
+}
+
+/// Validate a single component on its own. Offsets in the error are relative
+/// to `s`.
+pub(crate) fn validate_component(component: Component, s: &str) -> Result<(), Error> {
+    match component {
+        Component::Scheme => validate_scheme(s),
+        Component::Userinfo => validate(s, 0, component, is_userinfo_char),
+        Component::Host => parse_host(s, 0).map(|_| ()),
+        Component::Port => validate_port(s, 0),
+        Component::Path => validate(s, 0, component, is_path_char),
+        Component::Query | Component::Fragment => validate(s, 0, component, is_query_char),
+    }
+}
+
+fn validate_port(port: &str, offset: usize) -> Result<(), Error> {
+    if let Some((i, ch)) = port.char_indices().find(|(_, ch)| !ch.is_ascii_digit()) {
+        return Err(Error::InvalidChar {
+            component: Component::Port,
+            offset: offset + i,
+            ch,
+        });
+    }
+    if !port.is_empty() && port.parse::<u16>().is_err() {
+        return Err(Error::PortOutOfRange { offset });
+    }
+    Ok(())
+}
+
+fn validate_scheme(scheme: &str) -> Result<(), Error> {
+    let Some(first) = scheme.chars().next() else {
+        return Err(Error::EmptyScheme { offset: 0 });
+    };
+    if !first.is_ascii_alphabetic() {
+        return Err(Error::InvalidSchemeChar {
+            offset: 0,
+            ch:     first,
+        });
+    }
+    match scheme
+        .char_indices()
+        .find(|&(_, ch)| !(ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.')))
+    {
+        Some((offset, ch)) => Err(Error::InvalidSchemeChar { offset, ch }),
+        None => Ok(()),
+    }
+}
+
+/// Parse a `host`: an `IP-literal` in brackets, an `IPv4address` or a
+/// `reg-name`. `offset` is the position of `host` in the input.
+pub(crate) fn parse_host(host: &str, offset: usize) -> Result<Host<'_>, Error> {
+    let Some(literal) = host.strip_prefix('[') else {
+        validate(host, offset, Component::Host, |b| {
+            is_unreserved(b) || is_sub_delim(b)
+        })?;
+        return Ok(match host.parse::<Ipv4Addr>() {
+            Ok(addr) => Host::Ipv4(addr),
+            Err(_) => Host::RegName(host),
+        });
+    };
+    let end = literal
+        .find(']')
+        .ok_or(Error::UnterminatedIpLiteral { offset })?;
+    if let Some(ch) = literal[end + 1..].chars().next() {
+        return Err(Error::InvalidChar {
+            component: Component::Host,
+            offset: offset + end + 2,
+            ch,
+        });
+    }
+    parse_ip_literal(&literal[..end], offset)
+}
+
+/// `IP-literal` without the surrounding brackets: an `IPv6address`, an
+/// `IPv6addrz` ([RFC 6874]) or an `IPvFuture`. `offset` is the position of the
+/// opening bracket.
+///
+/// [RFC 6874]: https://datatracker.ietf.org/doc/html/rfc6874#section-2
+fn parse_ip_literal(literal: &str, offset: usize) -> Result<Host<'_>, Error> {
+    let invalid = Error::InvalidIpLiteral { offset };
+    if let Some(future) = literal.strip_prefix(['v', 'V']) {
+        let (version, address) = future.split_once('.').ok_or(invalid.clone())?;
+        let valid = !version.is_empty()
+            && version.bytes().all(|b| b.is_ascii_hexdigit())
+            && !address.is_empty()
+            && address
+                .bytes()
+                .all(|b| is_unreserved(b) || is_sub_delim(b) || b == b':');
+        return if valid {
+            Ok(Host::IpvFuture { version, address })
+        } else {
+            Err(invalid)
+        };
+    }
+
+    let (addr, zone) = match literal.find('%') {
+        Some(i) => {
+            let Some(zone) = literal[i..].strip_prefix("%25") else {
+                return Err(Error::InvalidPercentEncoding {
+                    component: Component::Host,
+                    offset:    offset + 1 + i,
+                });
+            };
+            if zone.is_empty() {
+                return Err(invalid);
+            }
+            validate(zone, offset + 1 + i + 3, Component::Host, is_unreserved)?;
+            (&literal[..i], Some(zone))
+        }
+        None => (literal, None),
+    };
+    let addr = addr.parse().map_err(|_| invalid)?;
+    Ok(Host::Ipv6 { addr, zone })
+}
+
+/// Check that every byte of `s` is either `allowed` or part of a
+/// `pct-encoded` triplet. `offset` is the position of `s` in the input.
+fn validate(
+    s: &str,
+    offset: usize,
+    component: Component,
+    allowed: impl Fn(u8) -> bool,
+) -> Result<(), Error> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let escape = bytes.get(i + 1..i + 3).unwrap_or(&bytes[i + 1..]);
+                if escape.len() != 2 || !escape.iter().all(u8::is_ascii_hexdigit) {
+                    return Err(Error::InvalidPercentEncoding {
+                        component,
+                        offset: offset + i,
+                    });
+                }
+                i += 3;
+            }
+            b if allowed(b) => i += 1,
+            _ => {
+                // `s` is a `str`, so `i` is always a char boundary here.
+                let ch = s[i..].chars().next().unwrap_or_default();
+                return Err(Error::InvalidChar {
+                    component,
+                    offset: offset + i,
+                    ch,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `unreserved = ALPHA / DIGIT / "-" / "." / "_" / "~"`
+pub(crate) const fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~')
+}
+
+/// `sub-delims = "!" / "$" / "&" / "'" / "(" / ")" / "*" / "+" / "," / ";" /
+/// "="`
+pub(crate) const fn is_sub_delim(b: u8) -> bool {
+    matches!(
+        b,
+        b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'='
+    )
+}
+
+/// `pchar = unreserved / pct-encoded / sub-delims / ":" / "@"`, less the
+/// `pct-encoded` part.
+pub(crate) const fn is_pchar(b: u8) -> bool {
+    is_unreserved(b) || is_sub_delim(b) || matches!(b, b':' | b'@')
+}
+
+const fn is_userinfo_char(b: u8) -> bool {
+    is_unreserved(b) || is_sub_delim(b) || b == b':'
+}
+
+const fn is_path_char(b: u8) -> bool {
+    is_pchar(b) || b == b'/'
+}
+
+/// Characters allowed verbatim in a query or fragment.
+const fn is_query_char(b: u8) -> bool {
+    is_pchar(b) || matches!(b, b'/' | b'?')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        for s in [
+            "",
+            "#",
+            "?",
+            "//",
+            "a:",
+            "foo://example.com:8042/over/there?name=ferret#nose",
+            "http://[v7.fe80::a+en1]/",
+            "http://[::ffff:192.0.2.1]:/",
+            "http://[fe80::1%25eth0]/",
+            "http://[fe80::1%25en%2F1]:8080/",
+            "http://host:65535/",
+            "file:///etc/hosts",
+            "../a/b:c?x/y?z#f/?",
+            "//user:pa%20ss@host:1/p",
+            "a%2Fb/c",
+        ] {
+            assert!(parse(s).is_ok(), "{s:?} should parse");
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        use Component::*;
+        for (s, err) in [
+            (
+                "http://exa mple.com/",
+                Error::InvalidChar {
+                    component: Host,
+                    offset:    10,
+                    ch:        ' ',
+                },
+            ),
+            (
+                "http://example.com/<script>",
+                Error::InvalidChar {
+                    component: Path,
+                    offset:    19,
+                    ch:        '<',
+                },
+            ),
+            (
+                "http://example.com/%zz",
+                Error::InvalidPercentEncoding {
+                    component: Path,
+                    offset:    19,
+                },
+            ),
+            (
+                "http://example.com/a?b=%2",
+                Error::InvalidPercentEncoding {
+                    component: Query,
+                    offset:    23,
+                },
+            ),
+            (
+                "http://[2001:db8::7/",
+                Error::UnterminatedIpLiteral { offset: 7 },
+            ),
+            (
+                "http://u@[2001:db8::7]x/",
+                Error::InvalidChar {
+                    component: Host,
+                    offset:    22,
+                    ch:        'x',
+                },
+            ),
+            ("http://[not-ipv6]/", Error::InvalidIpLiteral { offset: 7 }),
+            ("http://[v.x]/", Error::InvalidIpLiteral { offset: 7 }),
+            (
+                "http://[fe80::1%eth0]/",
+                Error::InvalidPercentEncoding {
+                    component: Host,
+                    offset:    15,
+                },
+            ),
+            (
+                "http://[fe80::1%25]/",
+                Error::InvalidIpLiteral { offset: 7 },
+            ),
+            (
+                "http://[fe80::1%25eth:0]/",
+                Error::InvalidChar {
+                    component: Host,
+                    offset:    21,
+                    ch:        ':',
+                },
+            ),
+            (
+                "http://[fe80::zz%25eth0]/",
+                Error::InvalidIpLiteral { offset: 7 },
+            ),
+            (
+                "http://host:80a/",
+                Error::InvalidChar {
+                    component: Port,
+                    offset:    14,
+                    ch:        'a',
+                },
+            ),
+            ("http://host:65536/", Error::PortOutOfRange { offset: 12 }),
+            (
+                "http://us@er@host/",
+                Error::InvalidChar {
+                    component: Host,
+                    offset:    12,
+                    ch:        '@',
+                },
+            ),
+            (
+                "1http://host/",
+                Error::InvalidSchemeChar {
+                    offset: 0,
+                    ch:     '1',
+                },
+            ),
+            (":foo", Error::EmptyScheme { offset: 0 }),
+            (
+                "a b:c",
+                Error::InvalidSchemeChar {
+                    offset: 1,
+                    ch:     ' ',
+                },
+            ),
+            (
+                "http://example.com/é",
+                Error::InvalidChar {
+                    component: Path,
+                    offset:    19,
+                    ch:        'é',
+                },
+            ),
+            (
+                "?query#frag#frag",
+                Error::InvalidChar {
+                    component: Fragment,
+                    offset:    11,
+                    ch:        '#',
+                },
+            ),
+        ] {
+            assert_eq!(parse(s), Err(err), "{s:?}");
+        }
+    }
+}
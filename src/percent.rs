@@ -0,0 +1,392 @@
+//! Percent-encoding and decoding as described in [RFC 3986 section 2.1].
+//!
+//! [RFC 3986 section 2.1]: https://datatracker.ietf.org/doc/html/rfc3986#section-2.1
+
+use std::borrow::Cow;
+
+use crate::parse::{is_pchar, is_sub_delim, is_unreserved};
+
+/// Build the set of every byte `$b` for which `$keep` is false.
+macro_rules! keeping {
+    ($b:ident => $keep:expr) => {{
+        let mut set = EncodeSet::ALL;
+        let mut $b: u8 = 0;
+        while $b < 128 {
+            if $keep {
+                set = set.remove($b);
+            }
+            $b += 1;
+        }
+        set
+    }};
+}
+
+/// A set of bytes that [`percent_encode`] will escape.
+///
+/// Bytes outside of ASCII can never appear unescaped in a URI, so they are
+/// always part of the set. Custom sets are built from the provided constants
+/// with [`EncodeSet::add`] and [`EncodeSet::remove`]:
+///
+/// ```
+/// use uri_rs::{EncodeSet, percent_encode};
+///
+/// const SET: EncodeSet = EncodeSet::CONTROLS.add(b' ').add(b'"');
+/// assert_eq!(percent_encode("say \"hi\"", &SET), "say%20%22hi%22");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodeSet {
+    ascii: u128,
+}
+
+impl EncodeSet {
+    /// Only non-ASCII bytes.
+    pub const NON_ASCII: Self = Self { ascii: 0 };
+    /// Every byte.
+    pub const ALL: Self = Self { ascii: u128::MAX };
+    /// ASCII control characters and non-ASCII bytes.
+    pub const CONTROLS: Self = Self {
+        ascii: ((1 << 0x20) - 1) | (1 << 0x7F),
+    };
+    /// Everything but `unreserved` characters. Safe anywhere in a URI.
+    pub const NON_UNRESERVED: Self = keeping!(b => is_unreserved(b));
+    /// Everything not allowed verbatim in `userinfo`.
+    pub const USERINFO: Self = keeping!(b => is_unreserved(b) || is_sub_delim(b) || b == b':');
+    /// Everything not allowed verbatim in a registered name.
+    pub const REG_NAME: Self = keeping!(b => is_unreserved(b) || is_sub_delim(b));
+    /// Everything not allowed verbatim in a single path segment, including
+    /// `/`.
+    pub const PATH_SEGMENT: Self = keeping!(b => is_pchar(b));
+    /// Everything not allowed verbatim in a path. `/` is left as-is.
+    pub const PATH: Self = keeping!(b => is_pchar(b) || b == b'/');
+    /// Everything not allowed verbatim in a query.
+    pub const QUERY: Self = keeping!(b => is_pchar(b) || b == b'/' || b == b'?');
+    /// Everything not allowed verbatim in a query, plus the `&`, `=` and `+`
+    /// that delimit `key=value` pairs.
+    pub const QUERY_PARAM: Self = Self::QUERY.add(b'&').add(b'=').add(b'+');
+    /// Everything not allowed verbatim in a fragment.
+    pub const FRAGMENT: Self = Self::QUERY;
+    /// Everything but ASCII alphanumerics and `*-._`, the set used by the
+    /// `application/x-www-form-urlencoded` byte serializer. See
+    /// [`form_urlencoded`](crate::form_urlencoded).
+    pub const FORM_URLENCODED: Self =
+        keeping!(b => b.is_ascii_alphanumeric() || matches!(b, b'*' | b'-' | b'.' | b'_'));
+
+    /// Add an ASCII byte to the set.
+    pub const fn add(self, b: u8) -> Self {
+        assert!(b.is_ascii(), "non-ASCII bytes are always encoded");
+        Self {
+            ascii: self.ascii | 1 << b,
+        }
+    }
+
+    /// Remove an ASCII byte from the set.
+    pub const fn remove(self, b: u8) -> Self {
+        assert!(b.is_ascii(), "non-ASCII bytes are always encoded");
+        Self {
+            ascii: self.ascii & !(1 << b),
+        }
+    }
+
+    /// Whether `b` will be percent-encoded.
+    pub const fn contains(&self, b: u8) -> bool {
+        !b.is_ascii() || self.ascii & 1 << b != 0
+    }
+}
+
+/// Percent-encode every byte of `input` that is in `set`.
+///
+/// ```
+/// use uri_rs::{EncodeSet, percent_encode};
+///
+/// assert_eq!(percent_encode("a/b c", &EncodeSet::PATH_SEGMENT), "a%2Fb%20c");
+/// assert_eq!(percent_encode("a/b c", &EncodeSet::PATH), "a/b%20c");
+/// ```
+pub fn percent_encode(input: impl AsRef<[u8]>, set: &EncodeSet) -> String {
+    let mut out = String::new();
+    percent_encode_to(input, set, &mut out);
+    out
+}
+
+/// Like [`percent_encode`], but append to `out` instead of allocating.
+pub fn percent_encode_to(input: impl AsRef<[u8]>, set: &EncodeSet, out: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let input = input.as_ref();
+    out.reserve(input.len());
+    for &b in input {
+        if set.contains(b) {
+            out.push('%');
+            out.push(char::from(HEX[usize::from(b >> 4)]));
+            out.push(char::from(HEX[usize::from(b & 0xF)]));
+        } else {
+            out.push(char::from(b));
+        }
+    }
+}
+
+/// An error returned by [`percent_decode_strict`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PercentDecodeError {
+    #[error("invalid percent-encoding at byte {offset}")]
+    InvalidEscape { offset: usize },
+    #[error("decoded text is not valid UTF-8 at byte {offset}")]
+    InvalidUtf8 { offset: usize },
+}
+
+impl PercentDecodeError {
+    /// Byte offset into the encoded input at which the error occurred.
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::InvalidEscape { offset } | Self::InvalidUtf8 { offset } => offset,
+        }
+    }
+
+    /// Move the offset `n` bytes later, for input that was sliced out of a
+    /// larger string.
+    pub(crate) fn offset_by(self, n: usize) -> Self {
+        match self {
+            Self::InvalidEscape { offset } => Self::InvalidEscape { offset: offset + n },
+            Self::InvalidUtf8 { offset } => Self::InvalidUtf8 { offset: offset + n },
+        }
+    }
+}
+
+/// Lazily decode `%XX` escapes, yielding raw bytes.
+///
+/// A `%` that isn't followed by two hex digits is kept as-is.
+///
+/// ```
+/// use uri_rs::percent_decode_iter;
+///
+/// assert!(percent_decode_iter("a%20b").eq(*b"a b"));
+/// ```
+pub fn percent_decode_iter(s: &(impl AsRef<[u8]> + ?Sized)) -> PercentDecode<'_> {
+    PercentDecode {
+        bytes: s.as_ref().iter(),
+    }
+}
+
+/// Iterator over percent-decoded bytes, created by [`percent_decode_iter`].
+#[derive(Debug, Clone)]
+pub struct PercentDecode<'a> {
+    bytes: std::slice::Iter<'a, u8>,
+}
+
+impl Iterator for PercentDecode<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let b = *self.bytes.next()?;
+        if b == b'%'
+            && let Some(decoded) = decode_escape(self.bytes.as_slice())
+        {
+            self.bytes.nth(1);
+            return Some(decoded);
+        }
+        Some(b)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bytes.len();
+        (len.div_ceil(3), Some(len))
+    }
+}
+
+/// Decode `%XX` escapes into raw bytes.
+///
+/// A `%` that isn't followed by two hex digits is kept as-is.
+pub fn percent_decode_to_bytes(s: impl AsRef<[u8]>) -> Vec<u8> {
+    percent_decode_iter(&s).collect()
+}
+
+/// Decode `%XX` escapes, replacing invalid UTF-8 with U+FFFD.
+///
+/// A `%` that isn't followed by two hex digits is kept as-is.
+///
+/// ```
+/// use uri_rs::percent_decode_lossy;
+///
+/// assert_eq!(percent_decode_lossy("caf%C3%A9%FF%"), "café\u{FFFD}%");
+/// ```
+pub fn percent_decode_lossy(s: impl AsRef<[u8]>) -> String {
+    match String::from_utf8(percent_decode_to_bytes(s)) {
+        Ok(s) => s,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    }
+}
+
+/// Decode `%XX` escapes, failing on a malformed escape or if the result is not
+/// valid UTF-8.
+///
+/// ```
+/// use uri_rs::{PercentDecodeError, percent_decode_strict};
+///
+/// assert_eq!(percent_decode_strict("caf%C3%A9").unwrap(), "café");
+/// assert_eq!(
+///     percent_decode_strict("caf%C3%"),
+///     Err(PercentDecodeError::InvalidEscape { offset: 6 })
+/// );
+/// assert_eq!(
+///     percent_decode_strict("caf%C3"),
+///     Err(PercentDecodeError::InvalidUtf8 { offset: 3 })
+/// );
+/// ```
+pub fn percent_decode_strict(s: impl AsRef<[u8]>) -> Result<String, PercentDecodeError> {
+    let s = s.as_ref();
+    let mut out = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'%' {
+            let b = decode_escape(&s[i + 1..])
+                .ok_or(PercentDecodeError::InvalidEscape { offset: i })?;
+            out.push(b);
+            i += 3;
+        } else {
+            out.push(s[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|e| {
+        // Every escape is valid by now, so walk the input again to find
+        // where the first invalid decoded byte came from.
+        let mut offset = 0;
+        for _ in 0..e.utf8_error().valid_up_to() {
+            offset += if s[offset] == b'%' { 3 } else { 1 };
+        }
+        PercentDecodeError::InvalidUtf8 { offset }
+    })
+}
+
+/// Decode `%XX` escapes, borrowing `s` if it contains none.
+///
+/// Fails like [`percent_decode_strict`].
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use uri_rs::percent_decode_cow;
+///
+/// assert!(matches!(percent_decode_cow("plain"), Ok(Cow::Borrowed("plain"))));
+/// assert_eq!(percent_decode_cow("a%2Bb").unwrap(), "a+b");
+/// ```
+pub fn percent_decode_cow(s: &str) -> Result<Cow<'_, str>, PercentDecodeError> {
+    if s.contains('%') {
+        percent_decode_strict(s).map(Cow::Owned)
+    } else {
+        Ok(Cow::Borrowed(s))
+    }
+}
+
+/// Decode `%XX` escapes, returning `None` on a malformed escape or if the
+/// result is not valid UTF-8.
+///
+/// See [`percent_decode_strict`] for the reason decoding failed.
+pub fn percent_decode(s: impl AsRef<str>) -> Option<String> {
+    percent_decode_strict(s.as_ref()).ok()
+}
+
+/// The byte encoded by the two hex digits at the start of `s`, which directly
+/// follow a `%`.
+pub(crate) fn decode_escape(s: &[u8]) -> Option<u8> {
+    fn hex(b: u8) -> Option<u8> {
+        char::from(b).to_digit(16).map(|d| d as u8)
+    }
+    match *s {
+        [hi, lo, ..] => Some(hex(hi)? << 4 | hex(lo)?),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uri;
+
+    #[test]
+    fn encode() {
+        let input = "user:pässword@host/a b?c#d%";
+        assert_eq!(
+            percent_encode(input, &EncodeSet::USERINFO),
+            "user:p%C3%A4ssword%40host%2Fa%20b%3Fc%23d%25"
+        );
+        assert_eq!(
+            percent_encode(input, &EncodeSet::PATH_SEGMENT),
+            "user:p%C3%A4ssword@host%2Fa%20b%3Fc%23d%25"
+        );
+        assert_eq!(
+            percent_encode(input, &EncodeSet::QUERY),
+            "user:p%C3%A4ssword@host/a%20b?c%23d%25"
+        );
+        assert_eq!(
+            percent_encode(input, &EncodeSet::NON_UNRESERVED),
+            "user%3Ap%C3%A4ssword%40host%2Fa%20b%3Fc%23d%25"
+        );
+        assert_eq!(percent_encode(b"\x00\xFF", &EncodeSet::NON_ASCII), "\0%FF");
+    }
+
+    #[test]
+    fn encoded_components_parse() {
+        let raw: String = (0..=u8::MAX).map(char::from).collect();
+        let path = percent_encode(&raw, &EncodeSet::PATH);
+        let query = percent_encode(&raw, &EncodeSet::QUERY);
+        let fragment = percent_encode(&raw, &EncodeSet::FRAGMENT);
+        let userinfo = percent_encode(&raw, &EncodeSet::USERINFO);
+        let s = format!("http://{userinfo}@host/{path}?{query}#{fragment}");
+        let uri = Uri::new(&s).unwrap();
+        assert_eq!(uri.path, Some(path.as_str()));
+        assert_eq!(uri.query, Some(query.as_str()));
+        assert_eq!(uri.fragment, Some(fragment.as_str()));
+    }
+
+    #[test]
+    fn decode() {
+        // Non-ASCII text before an escape used to be sliced by char index.
+        assert_eq!(percent_decode("日本%20語").unwrap(), "日本 語");
+        assert_eq!(percent_decode("%E6%97%A5%e6%9c%ac").unwrap(), "日本");
+        assert_eq!(percent_decode("%C3%A9").unwrap(), "é");
+        assert_eq!(percent_decode("%C3"), None);
+        assert_eq!(percent_decode("%%41"), None);
+        assert_eq!(percent_decode("é%"), None);
+
+        assert_eq!(percent_decode_to_bytes("%FF%zz%4"), b"\xFF%zz%4");
+        assert_eq!(percent_decode_lossy("%C3%28"), "\u{FFFD}(");
+        assert_eq!(
+            percent_decode_strict("ok%E6%97%A5%E6%97"),
+            Err(PercentDecodeError::InvalidUtf8 { offset: 11 })
+        );
+        assert_eq!(
+            percent_decode_strict("日%41%FF"),
+            Err(PercentDecodeError::InvalidUtf8 { offset: 6 })
+        );
+        assert_eq!(
+            percent_decode_strict("日%2"),
+            Err(PercentDecodeError::InvalidEscape { offset: 3 })
+        );
+    }
+
+    #[test]
+    fn decode_lazily() {
+        assert!(matches!(percent_decode_cow(""), Ok(Cow::Borrowed(""))));
+        assert!(matches!(
+            percent_decode_cow("key+1"),
+            Ok(Cow::Borrowed("key+1"))
+        ));
+        assert!(matches!(percent_decode_cow("%6Bey"), Ok(Cow::Owned(s)) if s == "key"));
+        assert_eq!(
+            percent_decode_cow("k%"),
+            Err(PercentDecodeError::InvalidEscape { offset: 1 })
+        );
+
+        let mut iter = percent_decode_iter("%41%4%%42");
+        assert_eq!(iter.size_hint(), (3, Some(9)));
+        assert_eq!(iter.next(), Some(b'A'));
+        assert_eq!(iter.collect::<Vec<_>>(), b"%4%B");
+    }
+
+    #[test]
+    fn round_trip() {
+        let raw = "ünïcödé / 100% ✓";
+        let encoded = percent_encode(raw, &EncodeSet::NON_UNRESERVED);
+        assert_eq!(percent_decode(&encoded).unwrap(), raw);
+        assert_eq!(percent_decode_to_bytes(&encoded), raw.as_bytes());
+    }
+}
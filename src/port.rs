@@ -0,0 +1,138 @@
+//! Numeric ports and the default ports of well-known schemes.
+
+use crate::{Uri, UriOwned};
+
+/// The port a scheme uses when a URI doesn't give one, from the IANA service
+/// name registry. Schemes are matched case-insensitively.
+///
+/// ```
+/// use uri_rs::default_port;
+///
+/// assert_eq!(default_port("HTTPS"), Some(443));
+/// assert_eq!(default_port("mailto"), None);
+/// ```
+pub fn default_port(scheme: &str) -> Option<u16> {
+    // Schemes are almost always lowercase already, so only allocate if not.
+    if scheme.bytes().any(|b| b.is_ascii_uppercase()) {
+        return default_port(&scheme.to_ascii_lowercase());
+    }
+    Some(match scheme {
+        "ftp" => 21,
+        "ssh" | "sftp" => 22,
+        "telnet" => 23,
+        "smtp" => 25,
+        "gopher" => 70,
+        "http" | "ws" => 80,
+        "pop" | "pop3" => 110,
+        "nntp" | "news" => 119,
+        "imap" => 143,
+        "snmp" => 161,
+        "ldap" => 389,
+        "https" | "wss" => 443,
+        "rtsp" => 554,
+        "nntps" => 563,
+        "ldaps" => 636,
+        "rsync" => 873,
+        "ftps" => 990,
+        "imaps" => 993,
+        "pop3s" => 995,
+        "mqtt" => 1883,
+        "nfs" => 2049,
+        "mysql" => 3306,
+        "sip" => 5060,
+        "sips" => 5061,
+        "xmpp" => 5222,
+        "postgres" | "postgresql" => 5432,
+        "amqps" => 5671,
+        "amqp" => 5672,
+        "coap" => 5683,
+        "coaps" => 5684,
+        "vnc" => 5900,
+        "redis" => 6379,
+        "irc" => 6667,
+        "ircs" => 6697,
+        "git" => 9418,
+        "mongodb" => 27017,
+        _ => return None,
+    })
+}
+
+impl Uri<'_> {
+    /// The port as a number, or `None` if there is no port, it is empty or it
+    /// isn't a decimal number in range.
+    ///
+    /// ```
+    /// use uri_rs::Uri;
+    ///
+    /// assert_eq!(Uri::new("http://host:8080/").unwrap().port_u16(), Some(8080));
+    /// assert_eq!(Uri::new("http://host:/").unwrap().port_u16(), None);
+    /// ```
+    pub fn port_u16(&self) -> Option<u16> {
+        let port = self.port?;
+        // `u16::from_str` would also take a leading `+`.
+        if !port.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        port.parse().ok()
+    }
+
+    /// The port as a number, falling back to the [default](default_port) for
+    /// the scheme if the port is missing or empty. An invalid port gives
+    /// `None` rather than the default.
+    ///
+    /// ```
+    /// use uri_rs::Uri;
+    ///
+    /// assert_eq!(Uri::new("https://host/").unwrap().port_or_default(), Some(443));
+    /// assert_eq!(Uri::new("https://host:8443/").unwrap().port_or_default(), Some(8443));
+    /// ```
+    pub fn port_or_default(&self) -> Option<u16> {
+        match self.port {
+            None | Some("") => default_port(self.scheme?),
+            Some(_) => self.port_u16(),
+        }
+    }
+}
+
+impl UriOwned {
+    /// The port as a number. See [`Uri::port_u16`].
+    pub fn port_u16(&self) -> Option<u16> {
+        self.as_ref().port_u16()
+    }
+
+    /// The port as a number, falling back to the default for the scheme. See
+    /// [`Uri::port_or_default`].
+    pub fn port_or_default(&self) -> Option<u16> {
+        self.as_ref().port_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::UriOwned;
+
+    #[test]
+    fn ports() {
+        let mut uri = UriOwned::new("ldap://[2001:db8::7]/c=GB").unwrap();
+        assert_eq!(uri.port_u16(), None);
+        assert_eq!(uri.port_or_default(), Some(389));
+
+        uri.port = Some("99999999".to_string());
+        assert_eq!(uri.port_u16(), None);
+        assert_eq!(uri.port_or_default(), None);
+
+        uri.port = Some("+80".to_string());
+        assert_eq!(uri.port_u16(), None);
+        assert_eq!(uri.port_or_default(), None);
+
+        uri.port = Some("0".to_string());
+        assert_eq!(uri.port_or_default(), Some(0));
+
+        let uri = UriOwned::new("//host/").unwrap();
+        assert_eq!(uri.port_or_default(), None);
+        let uri = UriOwned::new("foo://host:/").unwrap();
+        assert_eq!(uri.port_or_default(), None);
+        let uri = UriOwned::new("http://host:/").unwrap();
+        assert_eq!(uri.port_or_default(), Some(80));
+    }
+}
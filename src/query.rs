@@ -0,0 +1,733 @@
+//! Parsed query strings.
+
+use std::{borrow::Cow, fmt};
+
+use crate::{
+    ArrayFormat, EncodeSet, PercentDecodeError, UriOwned, percent_decode_cow, percent_decode_lossy,
+    percent_encode, percent_encode_to,
+};
+
+/// The `key=value` pairs of a query string, in their original order.
+///
+/// A key may appear more than once. A key without a `=` has no value, which
+/// is distinct from an empty value:
+///
+/// ```
+/// use uri_rs::Uri;
+///
+/// let uri = Uri::new("/search?tag=a&tag=b&flag&empty=").unwrap();
+/// let params = uri.get_query_parameters().unwrap();
+/// assert_eq!(params.get("tag"), Some(Some("a")));
+/// assert_eq!(params.get_all("tag").collect::<Vec<_>>(), [Some("a"), Some("b")]);
+/// assert_eq!(params.get("flag"), Some(None));
+/// assert_eq!(params.get("empty"), Some(Some("")));
+/// assert_eq!(params.get("missing"), None);
+/// ```
+///
+/// Keys and values borrow from the query unless decoding changed them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct QueryParameters<'a> {
+    pairs: Vec<(Cow<'a, str>, Option<Cow<'a, str>>)>,
+}
+
+impl<'a> QueryParameters<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Split `query` on `&` and `=` and percent-decode each key and value.
+    ///
+    /// `+` is kept as-is; use [`form_urlencoded::parse`](crate::form_urlencoded::parse)
+    /// for HTML form data.
+    ///
+    /// Pairs that fail to decode are skipped. Use [`parse_strict`] to reject
+    /// them or [`parse_lenient`] to keep them.
+    ///
+    /// [`parse_strict`]: Self::parse_strict
+    /// [`parse_lenient`]: Self::parse_lenient
+    pub fn parse(query: &'a str) -> Self {
+        let pairs = split_pairs(query, &['&'], '=')
+            .filter_map(|(_, key, value)| {
+                Some((
+                    percent_decode_cow(key).ok()?,
+                    match value {
+                        Some(value) => Some(percent_decode_cow(value).ok()?),
+                        None => None,
+                    },
+                ))
+            })
+            .collect();
+        Self { pairs }
+    }
+
+    /// Like [`parse`](Self::parse), but fail on the first key or value that
+    /// isn't validly percent-encoded UTF-8.
+    ///
+    /// ```
+    /// use uri_rs::{PercentDecodeError, QueryError, QueryParameters};
+    ///
+    /// let err = QueryParameters::parse_strict("a=1&b=%zz").unwrap_err();
+    /// assert_eq!(err.offset(), 4);
+    /// assert!(matches!(
+    ///     err,
+    ///     QueryError::Malformed {
+    ///         error: PercentDecodeError::InvalidEscape { offset: 6 },
+    ///         ..
+    ///     }
+    /// ));
+    /// ```
+    pub fn parse_strict(query: &'a str) -> Result<Self, QueryError> {
+        QueryConfig::new().parse(query)
+    }
+
+    /// Like [`parse`](Self::parse), but keep a key or value that fails to
+    /// decode as its raw, still-encoded text.
+    ///
+    /// ```
+    /// use uri_rs::QueryParameters;
+    ///
+    /// let params = QueryParameters::parse_lenient("a=%41&b=%zz");
+    /// assert_eq!(params.get("a"), Some(Some("A")));
+    /// assert_eq!(params.get("b"), Some(Some("%zz")));
+    /// ```
+    pub fn parse_lenient(query: &'a str) -> Self {
+        let decode = |s| percent_decode_cow(s).unwrap_or(Cow::Borrowed(s));
+        let pairs = split_pairs(query, &['&'], '=')
+            .map(|(_, key, value)| (decode(key), value.map(decode)))
+            .collect();
+        Self { pairs }
+    }
+
+    /// The value of the first pair with this key. `Some(None)` means the key
+    /// is present without a value.
+    pub fn get(&self, key: &str) -> Option<Option<&str>> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// The values of every pair with this key, in order.
+    pub fn get_all<'s>(&'s self, key: &'s str) -> impl Iterator<Item = Option<&'s str>> {
+        self.iter().filter(move |(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Every pair, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.pairs.iter().map(|(k, v)| (&**k, v.as_deref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Copy any borrowed keys and values so the parameters outlive the query.
+    pub fn into_owned(self) -> QueryParameters<'static> {
+        QueryParameters {
+            pairs: self
+                .pairs
+                .into_iter()
+                .map(|(k, v)| {
+                    (
+                        Cow::Owned(k.into_owned()),
+                        v.map(|v| Cow::Owned(v.into_owned())),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a, K, V> FromIterator<(K, Option<V>)> for QueryParameters<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<Cow<'a, str>>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, Option<V>)>>(iter: I) -> Self {
+        Self {
+            pairs: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.map(Into::into)))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for QueryParameters<'a> {
+    type Item = (Cow<'a, str>, Option<Cow<'a, str>>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+/// How [`QueryConfig`] handles a key that appears more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DuplicateKeys {
+    /// Keep only the first pair with the key.
+    First,
+    /// Keep only the last pair with the key.
+    Last,
+    /// Keep every pair.
+    #[default]
+    All,
+    /// Fail with [`QueryError::DuplicateKey`].
+    Error,
+}
+
+/// Options for parsing query strings that don't follow the usual
+/// `key=value&key=value` convention.
+///
+/// ```
+/// use uri_rs::{DuplicateKeys, QueryConfig};
+///
+/// let config = QueryConfig::new()
+///     .separators(&[';', '&'])
+///     .delimiter(':')
+///     .plus_as_space(true)
+///     .duplicates(DuplicateKeys::Last);
+/// let params = config.parse("a:1;b:x+y&a:2").unwrap();
+/// assert_eq!(
+///     params.iter().collect::<Vec<_>>(),
+///     [("b", Some("x y")), ("a", Some("2"))]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryConfig {
+    pub(crate) separators:    Vec<char>,
+    pub(crate) delimiter:     char,
+    pub(crate) plus_as_space: bool,
+    pub(crate) duplicates:    DuplicateKeys,
+    pub(crate) array_format:  ArrayFormat,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            separators:    vec!['&'],
+            delimiter:     '=',
+            plus_as_space: false,
+            duplicates:    DuplicateKeys::All,
+            array_format:  ArrayFormat::Indices,
+        }
+    }
+}
+
+impl QueryConfig {
+    /// Split pairs on `&` and keys from values on `=`, keep `+` as-is and
+    /// keep every duplicate key.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The characters that separate pairs. Defaults to `&`.
+    pub fn separators(mut self, separators: &[char]) -> Self {
+        self.separators = separators.to_vec();
+        self
+    }
+
+    /// The character that separates a key from its value. Defaults to `=`.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether to decode `+` as a space, as in form data. Off by default.
+    pub fn plus_as_space(mut self, plus_as_space: bool) -> Self {
+        self.plus_as_space = plus_as_space;
+        self
+    }
+
+    /// What to do with a key that appears more than once. Defaults to
+    /// [`DuplicateKeys::All`].
+    pub fn duplicates(mut self, duplicates: DuplicateKeys) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// How arrays are written by [`serialize_nested`](Self::serialize_nested),
+    /// and whether [`parse_nested`](Self::parse_nested) splits values on
+    /// commas. Defaults to [`ArrayFormat::Indices`].
+    pub fn array_format(mut self, array_format: ArrayFormat) -> Self {
+        self.array_format = array_format;
+        self
+    }
+
+    /// Parse `query`, failing on the first key or value that isn't validly
+    /// percent-encoded UTF-8 or, with [`DuplicateKeys::Error`], on the first
+    /// repeated key.
+    pub fn parse<'a>(&self, query: &'a str) -> Result<QueryParameters<'a>, QueryError> {
+        let mut pairs: Vec<(Cow<'a, str>, Option<Cow<'a, str>>)> = Vec::new();
+        for (offset, key, value) in split_pairs(query, &self.separators, self.delimiter) {
+            let value_offset = offset + key.len() + self.delimiter.len_utf8();
+            let key = self.decode(key, offset, offset)?;
+            let value = value
+                .map(|value| self.decode(value, offset, value_offset))
+                .transpose()?;
+            let existing = pairs.iter().position(|(k, _)| *k == key);
+            match (self.duplicates, existing) {
+                (DuplicateKeys::First, Some(_)) => continue,
+                (DuplicateKeys::Last, Some(i)) => {
+                    pairs.remove(i);
+                }
+                (DuplicateKeys::Error, Some(_)) => {
+                    return Err(QueryError::DuplicateKey {
+                        offset,
+                        key: key.into_owned(),
+                    });
+                }
+                _ => {}
+            }
+            pairs.push((key, value));
+        }
+        Ok(QueryParameters { pairs })
+    }
+
+    /// Percent-decode `s`, which starts `at` bytes into the query, in the
+    /// pair starting at `offset`.
+    pub(crate) fn decode<'a>(
+        &self,
+        s: &'a str,
+        offset: usize,
+        at: usize,
+    ) -> Result<Cow<'a, str>, QueryError> {
+        let decoded = if self.plus_as_space && s.contains('+') {
+            // Same length, so error offsets still line up.
+            percent_decode_cow(&s.replace('+', " ")).map(|s| Cow::Owned(s.into_owned()))
+        } else {
+            percent_decode_cow(s)
+        };
+        decoded.map_err(|error| QueryError::Malformed {
+            offset,
+            error: error.offset_by(at),
+        })
+    }
+}
+
+/// An error returned by [`QueryParameters::parse_strict`] and
+/// [`QueryConfig::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QueryError {
+    /// A key or value isn't validly percent-encoded UTF-8.
+    #[error("malformed query parameter at byte {offset}: {error}")]
+    Malformed {
+        offset: usize,
+        /// Why decoding failed, with an offset into the query.
+        #[source]
+        error:  PercentDecodeError,
+    },
+    /// A key appeared more than once with [`DuplicateKeys::Error`].
+    #[error("duplicate query parameter {key:?} at byte {offset}")]
+    DuplicateKey { offset: usize, key: String },
+}
+
+impl QueryError {
+    /// Byte offset into the query of the start of the offending pair.
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::Malformed { offset, .. } | Self::DuplicateKey { offset, .. } => offset,
+        }
+    }
+}
+
+/// Split `query` on any of `separators` and then on the first `delimiter`,
+/// yielding the byte offset of each pair along with its key and value.
+///
+/// Empty pairs, as in an empty query or `a=1&&b=2`, are skipped.
+pub(crate) fn split_pairs<'a>(
+    query: &'a str,
+    separators: &[char],
+    delimiter: char,
+) -> impl Iterator<Item = (usize, &'a str, Option<&'a str>)> {
+    let mut next = 0;
+    query
+        .split(separators)
+        .map(move |pair| {
+            let offset = next;
+            // Every separator is a single char, but not necessarily one byte.
+            next += pair.len();
+            next += query[next..].chars().next().map_or(0, char::len_utf8);
+            (offset, pair)
+        })
+        .filter(|(_, pair)| !pair.is_empty())
+        .map(move |(offset, pair)| match pair.split_once(delimiter) {
+            Some((key, value)) => (offset, key, Some(value)),
+            None => (offset, pair, None),
+        })
+}
+
+/// Serializes the pairs back into a percent-encoded query string.
+impl fmt::Display for QueryParameters<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let mut query = String::new();
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                query.push('&');
+            }
+            percent_encode_to(key, &EncodeSet::QUERY_PARAM, &mut query);
+            if let Some(value) = value {
+                query.push('=');
+                percent_encode_to(value, &EncodeSet::QUERY_PARAM, &mut query);
+            }
+        }
+        f.write_str(&query)
+    }
+}
+
+impl UriOwned {
+    /// Get query parameters. See [`Uri::get_query_parameters`](crate::Uri::get_query_parameters).
+    pub fn get_query_parameters(&self) -> Option<QueryParameters<'_>> {
+        self.as_ref().get_query_parameters()
+    }
+
+    /// Get query parameters using `config`. See
+    /// [`Uri::get_query_parameters_with`](crate::Uri::get_query_parameters_with).
+    pub fn get_query_parameters_with(
+        &self,
+        config: &QueryConfig,
+    ) -> Option<Result<QueryParameters<'_>, QueryError>> {
+        self.as_ref().get_query_parameters_with(config)
+    }
+
+    /// Get query parameters, decoding `+` as a space. See
+    /// [`Uri::get_form_parameters`](crate::Uri::get_form_parameters).
+    pub fn get_form_parameters(&self) -> Option<QueryParameters<'_>> {
+        self.as_ref().get_form_parameters()
+    }
+
+    /// Edit the query as a list of `key=value` pairs.
+    ///
+    /// The query is rewritten when the returned [`QueryMut`] is dropped.
+    /// Pairs that weren't touched keep their original encoding.
+    ///
+    /// ```
+    /// use uri_rs::UriOwned;
+    ///
+    /// let mut uri = UriOwned::new("https://api.example.com/items?limit=10&b=x+y").unwrap();
+    /// uri.query_mut().set("cursor", "a&b").append_pair("tag", "new");
+    /// assert_eq!(
+    ///     uri.to_string(),
+    ///     "https://api.example.com/items?limit=10&b=x+y&cursor=a%26b&tag=new"
+    /// );
+    ///
+    /// uri.query_mut().remove("cursor").sort();
+    /// assert_eq!(
+    ///     uri.to_string(),
+    ///     "https://api.example.com/items?b=x+y&limit=10&tag=new"
+    /// );
+    /// ```
+    pub fn query_mut(&mut self) -> QueryMut<'_> {
+        let pairs = split_pairs(self.query.as_deref().unwrap_or_default(), &['&'], '=')
+            .map(|(_, key, value)| (key.to_string(), value.map(str::to_string)))
+            .collect();
+        QueryMut {
+            uri: self,
+            pairs,
+            modified: false,
+        }
+    }
+}
+
+/// A mutable view of the query of a [`UriOwned`], created by
+/// [`UriOwned::query_mut`].
+///
+/// Keys and values passed in are unencoded and get percent-encoded; keys and
+/// values passed to callbacks are decoded. When dropped, the pairs are written
+/// back to the URI, and an empty list of pairs removes the query.
+#[derive(Debug)]
+pub struct QueryMut<'a> {
+    uri:      &'a mut UriOwned,
+    /// Still-encoded pairs.
+    pairs:    Vec<(String, Option<String>)>,
+    modified: bool,
+}
+
+impl QueryMut<'_> {
+    /// Add `key=value` after the existing pairs.
+    pub fn append_pair(&mut self, key: &str, value: &str) -> &mut Self {
+        self.push(key, Some(value))
+    }
+
+    /// Add `key`, without a value, after the existing pairs.
+    pub fn append_key(&mut self, key: &str) -> &mut Self {
+        self.push(key, None)
+    }
+
+    /// Replace the value of the first pair with this key and remove the rest,
+    /// or append `key=value` if there is none.
+    pub fn set(&mut self, key: &str, value: &str) -> &mut Self {
+        let value = percent_encode(value, &EncodeSet::QUERY_PARAM);
+        let mut found = false;
+        self.pairs.retain_mut(|(k, v)| {
+            if percent_decode_lossy(&*k) != key {
+                return true;
+            }
+            if found {
+                return false;
+            }
+            found = true;
+            *v = Some(value.clone());
+            true
+        });
+        self.modified = true;
+        if !found {
+            self.pairs
+                .push((percent_encode(key, &EncodeSet::QUERY_PARAM), Some(value)));
+        }
+        self
+    }
+
+    /// Remove every pair with this key.
+    pub fn remove(&mut self, key: &str) -> &mut Self {
+        self.retain(|k, _| k != key)
+    }
+
+    /// Keep only the pairs for which `keep` returns `true`.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, Option<&str>) -> bool) -> &mut Self {
+        self.pairs.retain(|(k, v)| {
+            keep(
+                &percent_decode_lossy(k),
+                v.as_deref().map(percent_decode_lossy).as_deref(),
+            )
+        });
+        self.modified = true;
+        self
+    }
+
+    /// Sort the pairs by key. Pairs with the same key keep their order.
+    pub fn sort(&mut self) -> &mut Self {
+        self.pairs
+            .sort_by_cached_key(|(k, _)| percent_decode_lossy(k));
+        self.modified = true;
+        self
+    }
+
+    /// Remove every pair.
+    pub fn clear(&mut self) -> &mut Self {
+        self.pairs.clear();
+        self.modified = true;
+        self
+    }
+
+    fn push(&mut self, key: &str, value: Option<&str>) -> &mut Self {
+        self.pairs.push((
+            percent_encode(key, &EncodeSet::QUERY_PARAM),
+            value.map(|v| percent_encode(v, &EncodeSet::QUERY_PARAM)),
+        ));
+        self.modified = true;
+        self
+    }
+}
+
+impl Drop for QueryMut<'_> {
+    fn drop(&mut self) {
+        if !self.modified {
+            return;
+        }
+        self.uri.query = (!self.pairs.is_empty()).then(|| {
+            let mut query = String::new();
+            for (i, (key, value)) in self.pairs.iter().enumerate() {
+                if i > 0 {
+                    query.push('&');
+                }
+                query.push_str(key);
+                if let Some(value) = value {
+                    query.push('=');
+                    query.push_str(value);
+                }
+            }
+            query
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let params = QueryParameters::parse("b=2&a=1&b=3&c&d=&%62=4&bad=%zz&e=f=g");
+        assert_eq!(
+            params.iter().collect::<Vec<_>>(),
+            [
+                ("b", Some("2")),
+                ("a", Some("1")),
+                ("b", Some("3")),
+                ("c", None),
+                ("d", Some("")),
+                ("b", Some("4")),
+                ("e", Some("f=g")),
+            ]
+        );
+        assert_eq!(params.get_all("b").count(), 3);
+        assert!(params.contains("c"));
+        assert!(!params.contains("bad"));
+        assert!(matches!(params.pairs[0].0, Cow::Borrowed(_)));
+        assert!(matches!(params.pairs[5].0, Cow::Owned(_)));
+        assert!(QueryParameters::parse("").is_empty());
+        assert_eq!(
+            QueryParameters::parse("&a=1&&b&")
+                .iter()
+                .collect::<Vec<_>>(),
+            [("a", Some("1")), ("b", None)]
+        );
+        assert_eq!(QueryParameters::parse_lenient("&&").len(), 0);
+        assert_eq!(
+            QueryConfig::new().parse("&&a=%zz"),
+            Err(QueryError::Malformed {
+                offset: 2,
+                error:  PercentDecodeError::InvalidEscape { offset: 4 },
+            })
+        );
+    }
+
+    #[test]
+    fn parse_malformed() {
+        let query = "ok=1&k%zz=v&a=%C3&b=%FF";
+        assert_eq!(
+            QueryParameters::parse_strict(query),
+            Err(QueryError::Malformed {
+                offset: 5,
+                error:  PercentDecodeError::InvalidEscape { offset: 6 },
+            })
+        );
+        assert_eq!(
+            QueryParameters::parse_strict("ok=1&a=b%C3").unwrap_err(),
+            QueryError::Malformed {
+                offset: 5,
+                error:  PercentDecodeError::InvalidUtf8 { offset: 8 },
+            }
+        );
+        assert_eq!(
+            QueryParameters::parse_strict("x&%2")
+                .unwrap_err()
+                .to_string(),
+            "malformed query parameter at byte 2: invalid percent-encoding at byte 2"
+        );
+        assert_eq!(
+            QueryParameters::parse_strict("a=%41&b").unwrap(),
+            QueryParameters::parse("a=%41&b")
+        );
+
+        let params = QueryParameters::parse_lenient(query);
+        assert_eq!(
+            params.iter().collect::<Vec<_>>(),
+            [
+                ("ok", Some("1")),
+                ("k%zz", Some("v")),
+                ("a", Some("%C3")),
+                ("b", Some("%FF")),
+            ]
+        );
+        assert_eq!(QueryParameters::parse(query).len(), 1);
+    }
+
+    #[test]
+    fn configured() {
+        let legacy = QueryConfig::new().separators(&[';']).delimiter(':');
+        assert_eq!(
+            legacy.parse("a:1;b;c:x=y&z;a:2").unwrap(),
+            QueryParameters::from_iter([
+                ("a", Some("1")),
+                ("b", None),
+                ("c", Some("x=y&z")),
+                ("a", Some("2")),
+            ])
+        );
+
+        let query = "k=1&j=a+b;k=%2B&k=3";
+        let config = QueryConfig::new().separators(&['&', ';']);
+        let values = |config: &QueryConfig| {
+            let params = config.parse(query).unwrap().into_owned();
+            params
+                .iter()
+                .map(|(k, v)| format!("{k}={}", v.unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&config), ["k=1", "j=a+b", "k=+", "k=3"]);
+        let config = config.plus_as_space(true);
+        assert_eq!(values(&config), ["k=1", "j=a b", "k=+", "k=3"]);
+        let config = config.duplicates(DuplicateKeys::First);
+        assert_eq!(values(&config), ["k=1", "j=a b"]);
+        let config = config.duplicates(DuplicateKeys::Last);
+        assert_eq!(values(&config), ["j=a b", "k=3"]);
+        let config = config.duplicates(DuplicateKeys::Error);
+        assert_eq!(
+            config.parse(query),
+            Err(QueryError::DuplicateKey {
+                offset: 10,
+                key:    "k".to_string(),
+            })
+        );
+        assert_eq!(
+            config.parse("a=1&%61=2").unwrap_err().to_string(),
+            "duplicate query parameter \"a\" at byte 4"
+        );
+
+        // Offsets account for multi-byte separators and delimiters.
+        let config = QueryConfig::new().separators(&['¦']).delimiter('→');
+        assert_eq!(
+            config.parse("a→1¦b→%zz"),
+            Err(QueryError::Malformed {
+                offset: 7,
+                error:  PercentDecodeError::InvalidEscape { offset: 11 },
+            })
+        );
+
+        let uri = UriOwned::new("/p?a:1;b:2").unwrap();
+        let params = uri.get_query_parameters_with(&legacy).unwrap().unwrap();
+        assert_eq!(params.get("b"), Some(Some("2")));
+    }
+
+    #[test]
+    fn serialize() {
+        let params =
+            QueryParameters::from_iter([("a b", Some("1&2")), ("flag", None), ("x", Some(""))]);
+        let query = params.to_string();
+        assert_eq!(query, "a%20b=1%262&flag&x=");
+        assert_eq!(QueryParameters::parse(&query), params);
+        assert_eq!(params.clone().into_owned(), params);
+    }
+
+    #[test]
+    fn edit() {
+        let mut uri = UriOwned::new("/p?a=1&b&&c=%FF&a=2#f").unwrap();
+        uri.query_mut();
+        assert_eq!(uri.query.as_deref(), Some("a=1&b&&c=%FF&a=2"));
+
+        uri.query_mut().set("a", "x y").append_key("d=e");
+        assert_eq!(uri.query.as_deref(), Some("a=x%20y&b&c=%FF&d%3De"));
+        uri.query_mut().retain(|_, v| v != Some("\u{FFFD}"));
+        assert_eq!(uri.query.as_deref(), Some("a=x%20y&b&d%3De"));
+        uri.query_mut().append_pair("a", "+").sort();
+        assert_eq!(uri.query.as_deref(), Some("a=x%20y&a=%2B&b&d%3De"));
+        assert_eq!(
+            uri.get_query_parameters()
+                .unwrap()
+                .get_all("a")
+                .collect::<Vec<_>>(),
+            [Some("x y"), Some("+")]
+        );
+        uri.query_mut().remove("a").remove("b");
+        assert_eq!(uri.to_string(), "/p?d%3De#f");
+        uri.query_mut().clear();
+        assert_eq!(uri.to_string(), "/p#f");
+
+        for (input, expected) in [
+            ("/p?", "/p?k=v"),
+            ("/p?a=1&", "/p?a=1&k=v"),
+            ("/p?&&", "/p?k=v"),
+        ] {
+            let mut uri = UriOwned::new(input).unwrap();
+            uri.query_mut().append_pair("k", "v");
+            assert_eq!(uri.to_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,1028 @@
+//! Typed query strings with [`serde`].
+//!
+//! Each field of a struct maps to a query parameter. A field holding a
+//! sequence maps to a parameter repeated once per element, and a parameter
+//! present without a value deserializes to `true` or an empty string.
+
+use std::borrow::Cow;
+
+use serde::{
+    Deserialize, Serialize,
+    de::{
+        self, IntoDeserializer, Unexpected, Visitor,
+        value::{CowStrDeserializer, SeqDeserializer},
+    },
+    forward_to_deserialize_any,
+    ser::{self, Impossible},
+};
+
+use crate::{QueryConfig, QueryError, QueryParameters};
+
+/// An error returned by [`from_query`] and [`to_query`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QuerySerdeError {
+    /// The query isn't validly percent-encoded.
+    #[error(transparent)]
+    Query(#[from] QueryError),
+    #[error("missing query parameter {field:?}")]
+    MissingField { field: String },
+    #[error("invalid query parameter {field:?}: {message}")]
+    InvalidField { field: String, message: String },
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl QuerySerdeError {
+    /// The name of the field the error is about, if it is about one.
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Self::MissingField { field } | Self::InvalidField { field, .. } => Some(field),
+            Self::Query(_) | Self::Custom(_) => None,
+        }
+    }
+
+    /// Attribute an error raised while handling `field`'s value to it.
+    fn for_field(self, field: &str) -> Self {
+        match self {
+            Self::Custom(message) => Self::InvalidField {
+                field: field.to_string(),
+                message,
+            },
+            e => e,
+        }
+    }
+}
+
+impl de::Error for QuerySerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::MissingField {
+            field: field.to_string(),
+        }
+    }
+}
+
+impl ser::Error for QuerySerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+/// Deserialize a struct or map from a query, usually
+/// [`Uri::query`](crate::Uri::query).
+///
+/// The query is parsed with [`QueryParameters::parse_strict`], and a missing
+/// query is treated like an empty one. Strings borrow from the query where
+/// possible.
+///
+/// ```
+/// use serde::Deserialize;
+/// use uri_rs::{Uri, from_query};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Search<'a> {
+///     q:       &'a str,
+///     #[serde(default)]
+///     page:    u32,
+///     tag:     Vec<String>,
+///     verbose: bool,
+/// }
+///
+/// let uri = Uri::new("/search?q=rust&tag=web&tag=uri&verbose").unwrap();
+/// let search: Search = from_query(uri.query).unwrap();
+/// assert_eq!((search.q, search.page), ("rust", 0));
+/// assert_eq!(search.tag, ["web", "uri"]);
+/// assert!(search.verbose);
+///
+/// let err = from_query::<Search>("q=rust&page=two").unwrap_err();
+/// assert_eq!(err.field(), Some("page"));
+/// assert_eq!(
+///     err.to_string(),
+///     "invalid query parameter \"page\": invalid value: string \"two\", expected u32"
+/// );
+/// ```
+pub fn from_query<'de, T: Deserialize<'de>>(
+    query: impl Into<Option<&'de str>>,
+) -> Result<T, QuerySerdeError> {
+    from_query_with(query, &QueryConfig::new())
+}
+
+/// Like [`from_query`], but parse the query as described by `config`.
+pub fn from_query_with<'de, T: Deserialize<'de>>(
+    query: impl Into<Option<&'de str>>,
+    config: &QueryConfig,
+) -> Result<T, QuerySerdeError> {
+    let params = match query.into() {
+        Some(query) => config.parse(query)?,
+        None => QueryParameters::new(),
+    };
+    // Group repeated keys, keeping the order in which keys first appear.
+    let mut fields: Vec<Field> = Vec::new();
+    for (key, value) in params {
+        match fields.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, values)) => values.push(value),
+            None => fields.push((key, vec![value])),
+        }
+    }
+    T::deserialize(QueryDeserializer {
+        fields: fields.into_iter(),
+        value:  None,
+    })
+}
+
+type Field<'de> = (Cow<'de, str>, Vec<Option<Cow<'de, str>>>);
+
+/// The query as a map of keys to values.
+struct QueryDeserializer<'de> {
+    fields: std::vec::IntoIter<Field<'de>>,
+    /// The value of the key last returned by `next_key_seed`.
+    value:  Option<ValueDeserializer<'de>>,
+}
+
+impl<'de> de::Deserializer<'de> for QueryDeserializer<'de> {
+    type Error = QuerySerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> de::MapAccess<'de> for QueryDeserializer<'de> {
+    type Error = QuerySerdeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some((key, values)) = self.fields.next() else {
+            return Ok(None);
+        };
+        self.value = Some(ValueDeserializer {
+            key: key.clone(),
+            values,
+        });
+        let key: CowStrDeserializer<QuerySerdeError> = key.into_deserializer();
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        let key = value.key.clone();
+        seed.deserialize(value).map_err(|e| e.for_field(&key))
+    }
+}
+
+/// Every value of one key.
+struct ValueDeserializer<'de> {
+    key:    Cow<'de, str>,
+    values: Vec<Option<Cow<'de, str>>>,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    /// The first value, or an empty string if the key has no value.
+    fn into_str(self) -> Cow<'de, str> {
+        self.values.into_iter().next().flatten().unwrap_or_default()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, QuerySerdeError> for ValueDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Parse the first value with [`str::parse`].
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            let s = self.into_str();
+            match s.parse() {
+                Ok(v) => visitor.$visit(v),
+                Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&s), &visitor)),
+            }
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = QuerySerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.into_str() {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // A key without a value is a flag.
+        if let [None] = self.values[..] {
+            return visitor.visit_bool(true);
+        }
+        let s = self.into_str();
+        match s.parse() {
+            Ok(b) => visitor.visit_bool(b),
+            Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&s), &visitor)),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let key = self.key;
+        let values = self.values.into_iter().map(|value| ValueDeserializer {
+            key:    key.clone(),
+            values: vec![value],
+        });
+        de::Deserializer::deserialize_any(SeqDeserializer::new(values), visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let variant: CowStrDeserializer<QuerySerdeError> = self.into_str().into_deserializer();
+        visitor.visit_enum(variant)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct tuple_struct map struct identifier
+    }
+}
+
+/// Serialize a struct or map into a query string.
+///
+/// Keys and values are percent-encoded like [`QueryParameters`]' `Display`.
+/// `None` fields are left out, sequences repeat their key and unit values
+/// become a key without a value.
+///
+/// ```
+/// use serde::Serialize;
+/// use uri_rs::to_query;
+///
+/// #[derive(Serialize)]
+/// struct Search<'a> {
+///     q:      &'a str,
+///     page:   Option<u32>,
+///     tag:    &'a [&'a str],
+///     cursor: Option<&'a str>,
+/// }
+///
+/// let query = to_query(&Search {
+///     q:      "a&b c",
+///     page:   Some(2),
+///     tag:    &["web", "uri"],
+///     cursor: None,
+/// })
+/// .unwrap();
+/// assert_eq!(query, "q=a%26b%20c&page=2&tag=web&tag=uri");
+/// ```
+pub fn to_query<T: Serialize + ?Sized>(value: &T) -> Result<String, QuerySerdeError> {
+    let mut pairs = Vec::new();
+    value.serialize(QuerySerializer { pairs: &mut pairs })?;
+    Ok(QueryParameters::from_iter(pairs).to_string())
+}
+
+type Pairs = Vec<(String, Option<String>)>;
+
+fn unsupported(what: &str) -> QuerySerdeError {
+    QuerySerdeError::Custom(format!("{what} can't be serialized into a query"))
+}
+
+/// Reject every method that takes a value.
+macro_rules! unsupported {
+    ($what:literal: $($method:ident($ty:ty),)*) => {$(
+        fn $method(self, _: $ty) -> Result<Self::Ok, Self::Error> {
+            Err(unsupported($what))
+        }
+    )*};
+}
+
+/// The query as a whole, which must be a struct or map.
+struct QuerySerializer<'a> {
+    pairs: &'a mut Pairs,
+}
+
+impl<'a> ser::Serializer for QuerySerializer<'a> {
+    type Ok = ();
+    type Error = QuerySerdeError;
+    type SerializeSeq = Impossible<(), QuerySerdeError>;
+    type SerializeTuple = Impossible<(), QuerySerdeError>;
+    type SerializeTupleStruct = Impossible<(), QuerySerdeError>;
+    type SerializeTupleVariant = Impossible<(), QuerySerdeError>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = Impossible<(), QuerySerdeError>;
+
+    unsupported! { "a bare value":
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_none(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), QuerySerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> Result<(), QuerySerdeError> {
+        Err(unsupported("a bare value"))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        Err(unsupported("an enum"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, QuerySerdeError> {
+        Err(unsupported("a bare sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, QuerySerdeError> {
+        Err(unsupported("a bare tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, QuerySerdeError> {
+        Err(unsupported("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, QuerySerdeError> {
+        Err(unsupported("an enum"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, QuerySerdeError> {
+        Ok(MapSerializer {
+            pairs: self.pairs,
+            key:   None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer<'a>, QuerySerdeError> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, QuerySerdeError> {
+        Err(unsupported("an enum"))
+    }
+}
+
+/// The fields of the query.
+struct MapSerializer<'a> {
+    pairs: &'a mut Pairs,
+    /// The key last passed to `serialize_key`.
+    key:   Option<String>,
+}
+
+impl MapSerializer<'_> {
+    fn field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        value
+            .serialize(ValueSerializer {
+                key,
+                pairs: self.pairs,
+            })
+            .map_err(|e| e.for_field(key))
+    }
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Ok = ();
+    type Error = QuerySerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), QuerySerdeError> {
+        self.key = Some(key.serialize(PartSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), QuerySerdeError> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.field(&key, value)
+    }
+
+    fn end(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer<'_> {
+    type Ok = ();
+    type Error = QuerySerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+}
+
+/// The value of one field, pushed as zero or more pairs with its key.
+struct ValueSerializer<'a> {
+    key:   &'a str,
+    pairs: &'a mut Pairs,
+}
+
+impl ValueSerializer<'_> {
+    fn push(self, value: Option<String>) -> Result<(), QuerySerdeError> {
+        self.pairs.push((self.key.to_string(), value));
+        Ok(())
+    }
+}
+
+/// Push the value as formatted by [`PartSerializer`].
+macro_rules! serialize_part {
+    ($($method:ident($ty:ty),)*) => {$(
+        fn $method(self, v: $ty) -> Result<(), QuerySerdeError> {
+            let part = PartSerializer.$method(v)?;
+            self.push(Some(part))
+        }
+    )*};
+}
+
+impl<'a> ser::Serializer for ValueSerializer<'a> {
+    type Ok = ();
+    type Error = QuerySerdeError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), QuerySerdeError>;
+    type SerializeMap = Impossible<(), QuerySerdeError>;
+    type SerializeStruct = Impossible<(), QuerySerdeError>;
+    type SerializeStructVariant = Impossible<(), QuerySerdeError>;
+
+    serialize_part! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_none(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), QuerySerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), QuerySerdeError> {
+        self.push(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), QuerySerdeError> {
+        self.push(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<(), QuerySerdeError> {
+        let part = PartSerializer.serialize_unit_variant(name, index, variant)?;
+        self.push(Some(part))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, QuerySerdeError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, QuerySerdeError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self, QuerySerdeError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, QuerySerdeError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, QuerySerdeError> {
+        Err(unsupported("a nested map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, QuerySerdeError> {
+        Err(unsupported("a nested struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, QuerySerdeError> {
+        Err(unsupported("an enum with data"))
+    }
+}
+
+/// Every element of a sequence is pushed with the sequence's key.
+impl ser::SerializeSeq for ValueSerializer<'_> {
+    type Ok = ();
+    type Error = QuerySerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        value.serialize(ValueSerializer {
+            key:   self.key,
+            pairs: self.pairs,
+        })
+    }
+
+    fn end(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for ValueSerializer<'_> {
+    type Ok = ();
+    type Error = QuerySerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), QuerySerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for ValueSerializer<'_> {
+    type Ok = ();
+    type Error = QuerySerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), QuerySerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), QuerySerdeError> {
+        Ok(())
+    }
+}
+
+/// A single key or value, formatted as an unencoded string.
+struct PartSerializer;
+
+/// Format the value with [`ToString`].
+macro_rules! serialize_display {
+    ($($method:ident($ty:ty),)*) => {$(
+        fn $method(self, v: $ty) -> Result<String, QuerySerdeError> {
+            Ok(v.to_string())
+        }
+    )*};
+}
+
+impl ser::Serializer for PartSerializer {
+    type Ok = String;
+    type Error = QuerySerdeError;
+    type SerializeSeq = Impossible<String, QuerySerdeError>;
+    type SerializeTuple = Impossible<String, QuerySerdeError>;
+    type SerializeTupleStruct = Impossible<String, QuerySerdeError>;
+    type SerializeTupleVariant = Impossible<String, QuerySerdeError>;
+    type SerializeMap = Impossible<String, QuerySerdeError>;
+    type SerializeStruct = Impossible<String, QuerySerdeError>;
+    type SerializeStructVariant = Impossible<String, QuerySerdeError>;
+
+    serialize_display! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<String, QuerySerdeError> {
+        String::from_utf8(v.to_vec()).map_err(|_| unsupported("non-UTF-8 bytes"))
+    }
+
+    fn serialize_none(self) -> Result<String, QuerySerdeError> {
+        Err(unsupported("a missing key"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, QuerySerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, QuerySerdeError> {
+        Err(unsupported("a unit key"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, QuerySerdeError> {
+        Err(unsupported("a unit key"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, QuerySerdeError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, QuerySerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, QuerySerdeError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, QuerySerdeError> {
+        Err(unsupported("a sequence key"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, QuerySerdeError> {
+        Err(unsupported("a tuple key"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, QuerySerdeError> {
+        Err(unsupported("a tuple key"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, QuerySerdeError> {
+        Err(unsupported("an enum with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, QuerySerdeError> {
+        Err(unsupported("a map key"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, QuerySerdeError> {
+        Err(unsupported("a struct key"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, QuerySerdeError> {
+        Err(unsupported("an enum with data"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{PercentDecodeError, UriOwned};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+        Asc,
+        Desc,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Params {
+        name:   String,
+        limit:  Option<u16>,
+        #[serde(default)]
+        ids:    Vec<u64>,
+        order:  Order,
+        ratio:  f64,
+        active: bool,
+    }
+
+    #[test]
+    fn deserialize() {
+        let params: Params =
+            from_query("name=caf%C3%A9&ids=1&order=desc&ids=2&ratio=0.5&active&unknown=x").unwrap();
+        assert_eq!(
+            params,
+            Params {
+                name:   "café".to_string(),
+                limit:  None,
+                ids:    vec![1, 2],
+                order:  Order::Desc,
+                ratio:  0.5,
+                active: true,
+            }
+        );
+
+        let uri = UriOwned::new("/?a=1&b&a=2").unwrap();
+        let map: BTreeMap<String, Vec<String>> = from_query(uri.query.as_deref()).unwrap();
+        assert_eq!(map["a"], ["1", "2"]);
+        assert_eq!(map["b"], [""]);
+        let empty: BTreeMap<String, String> = from_query(None).unwrap();
+        assert!(empty.is_empty());
+        let empty: BTreeMap<String, String> = from_query("").unwrap();
+        assert!(empty.is_empty());
+        let map: BTreeMap<String, String> = from_query("&a=1&&").unwrap();
+        assert_eq!(map.len(), 1);
+
+        #[derive(Deserialize)]
+        struct Borrowed<'a> {
+            q: &'a str,
+        }
+        let query = String::from("q=plain");
+        let borrowed: Borrowed = from_query(query.as_str()).unwrap();
+        assert_eq!(borrowed.q, "plain");
+
+        let legacy = QueryConfig::new().separators(&[';']).plus_as_space(true);
+        let map: BTreeMap<String, String> = from_query_with("a=x+y;b=z", &legacy).unwrap();
+        assert_eq!(map["a"], "x y");
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        let base = "name=n&order=asc&ratio=1&active=true";
+        let error = |extra: &str| {
+            from_query::<Params>(format!("{base}&{extra}").as_str())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("limit=70000"),
+            "invalid query parameter \"limit\": invalid value: string \"70000\", expected u16"
+        );
+        assert_eq!(
+            error("ids=1&ids=x"),
+            "invalid query parameter \"ids\": invalid value: string \"x\", expected u64"
+        );
+        assert_eq!(
+            from_query::<Params>("name=n&order=up&ratio=1&active")
+                .unwrap_err()
+                .field(),
+            Some("order")
+        );
+        assert_eq!(
+            from_query::<Params>("name=n&ratio=1&active").unwrap_err(),
+            QuerySerdeError::MissingField {
+                field: "order".to_string(),
+            }
+        );
+        assert_eq!(
+            from_query::<Params>("name=%zz").unwrap_err(),
+            QuerySerdeError::Query(QueryError::Malformed {
+                offset: 0,
+                error:  PercentDecodeError::InvalidEscape { offset: 5 },
+            })
+        );
+        assert_eq!(
+            from_query::<Params>("name=n&order=asc&ratio=1&active=yes")
+                .unwrap_err()
+                .to_string(),
+            "invalid query parameter \"active\": invalid value: string \"yes\", expected a boolean"
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        let params = Params {
+            name:   "a b&c".to_string(),
+            limit:  None,
+            ids:    vec![3, 4],
+            order:  Order::Asc,
+            ratio:  1.5,
+            active: false,
+        };
+        let query = to_query(&params).unwrap();
+        assert_eq!(
+            query,
+            "name=a%20b%26c&ids=3&ids=4&order=asc&ratio=1.5&active=false"
+        );
+        assert_eq!(from_query::<Params>(query.as_str()).unwrap(), params);
+
+        let mut map = BTreeMap::new();
+        map.insert(1, ());
+        map.insert(2, ());
+        assert_eq!(to_query(&map).unwrap(), "1&2");
+
+        #[derive(Serialize)]
+        struct Nested {
+            inner: BTreeMap<String, String>,
+        }
+        let err = to_query(&Nested {
+            inner: BTreeMap::new(),
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid query parameter \"inner\": a nested map can't be serialized into a query"
+        );
+        assert!(to_query(&1).is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Reference resolution as described in [RFC 3986 section 5].
+//!
+//! [RFC 3986 section 5]: https://datatracker.ietf.org/doc/html/rfc3986#section-5
+
+use crate::{Error, Uri, UriOwned};
+
+impl Uri<'_> {
+    /// Resolve `reference` against `self` as its base URI, following
+    /// [RFC 3986 section 5.2].
+    ///
+    /// `self` should be an absolute URI; if it has no scheme the result won't
+    /// have one either unless `reference` does.
+    ///
+    /// ```
+    /// use uri_rs::Uri;
+    ///
+    /// let base = Uri::new("http://a/b/c/d;p?q").unwrap();
+    /// let reference = Uri::new("../g?x#s").unwrap();
+    /// assert_eq!(base.resolve(&reference).to_string(), "http://a/b/g?x#s");
+    /// ```
+    ///
+    /// [RFC 3986 section 5.2]: https://datatracker.ietf.org/doc/html/rfc3986#section-5.2
+    pub fn resolve(&self, reference: &Uri) -> UriOwned {
+        let (scheme, authority, path, query);
+        if reference.scheme.is_some() {
+            scheme = reference.scheme;
+            authority = Authority::of(reference);
+            path = remove_dot_segments(&reference.full_path());
+            query = reference.query;
+        } else {
+            scheme = self.scheme;
+            if reference.host.is_some() {
+                authority = Authority::of(reference);
+                path = remove_dot_segments(&reference.full_path());
+                query = reference.query;
+            } else {
+                authority = Authority::of(self);
+                let reference_path = reference.full_path();
+                if reference_path.is_empty() {
+                    path = self.full_path();
+                    query = reference.query.or(self.query);
+                } else {
+                    path = if reference_path.starts_with('/') {
+                        remove_dot_segments(&reference_path)
+                    } else {
+                        remove_dot_segments(&merge(self, &reference_path))
+                    };
+                    query = reference.query;
+                }
+            }
+        }
+
+        UriOwned::from_parts(scheme, authority, path, query, reference.fragment)
+    }
+
+    /// Compute the shortest relative reference that [resolves](Self::resolve)
+    /// against `self` to `target`.
+    ///
+    /// If no relative reference can reach `target` (for example because the
+    /// schemes differ), `target` itself is returned.
+    ///
+    /// ```
+    /// use uri_rs::Uri;
+    ///
+    /// let base = Uri::new("https://example.com/docs/guide/intro.html").unwrap();
+    /// let target = Uri::new("https://example.com/docs/api/index.html?v=2").unwrap();
+    /// let relative = base.make_relative(&target);
+    /// assert_eq!(relative.to_string(), "../api/index.html?v=2");
+    /// assert_eq!(base.resolve(&relative.as_ref()), target.into());
+    /// ```
+    pub fn make_relative(&self, target: &Uri) -> UriOwned {
+        if self.scheme != target.scheme {
+            return (*target).into();
+        }
+        let authority = Authority::of(target);
+        let target_path = remove_dot_segments(&target.full_path());
+        let network_path = || {
+            UriOwned::from_parts(
+                None,
+                authority,
+                target_path.clone(),
+                target.query,
+                target.fragment,
+            )
+        };
+        if (self.userinfo, self.host, self.port) != (target.userinfo, target.host, target.port) {
+            return if authority.is_some() {
+                network_path()
+            } else {
+                (*target).into()
+            };
+        }
+
+        let base_path = self.full_path();
+        if base_path == target_path && (self.query == target.query || target.query.is_some()) {
+            // An empty path keeps the base path as written, dot segments and
+            // all, and the base query unless the reference has its own.
+            let query = target.query.filter(|_| self.query != target.query);
+            return UriOwned::from_parts(None, None, String::new(), query, target.fragment);
+        }
+        if target_path.is_empty() {
+            // Only an empty reference path leaves the path empty, and that
+            // would keep the base query.
+            return if authority.is_some() {
+                network_path()
+            } else {
+                (*target).into()
+            };
+        }
+
+        let mut base_path = remove_dot_segments(&base_path);
+        if self.host.is_some() && base_path.is_empty() {
+            // Merging with an empty base path yields an absolute path.
+            base_path.push('/');
+        }
+        if !base_path.starts_with('/') || !target_path.starts_with('/') {
+            return (*target).into();
+        }
+        let relative = relative_path(&base_path, &target_path);
+        let path = if relative.len() <= target_path.len() || target_path.starts_with("//") {
+            relative
+        } else {
+            target_path
+        };
+        UriOwned::from_parts(None, None, path, target.query, target.fragment)
+    }
+
+    /// The path as defined by RFC 3986, including the `/` that separates it
+    /// from the authority.
+    pub(crate) fn full_path(&self) -> String {
+        match (self.host, self.path) {
+            (Some(_), Some(path)) => format!("/{path}"),
+            (Some(_), None) => String::new(),
+            (None, path) => path.unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl UriOwned {
+    /// Parse `reference` and resolve it against `self`.
+    ///
+    /// See [`Uri::resolve`].
+    ///
+    /// ```
+    /// use uri_rs::UriOwned;
+    ///
+    /// let page = UriOwned::new("https://example.com/docs/guide/").unwrap();
+    /// assert_eq!(
+    ///     page.join("../a?b").unwrap().to_string(),
+    ///     "https://example.com/docs/a?b"
+    /// );
+    /// ```
+    pub fn join(&self, reference: &str) -> Result<UriOwned, Error> {
+        Ok(self.as_ref().resolve(&Uri::new(reference)?))
+    }
+
+    /// Assemble a URI from components, converting an RFC 3986 `path` into
+    /// the representation used by [`Uri::path`].
+    pub(crate) fn from_parts(
+        scheme: Option<&str>,
+        authority: Option<Authority>,
+        path: String,
+        query: Option<&str>,
+        fragment: Option<&str>,
+    ) -> Self {
+        let (userinfo, host, port, path) = match authority {
+            Some(Authority {
+                userinfo,
+                host,
+                port,
+            }) => {
+                let path = path.strip_prefix('/').map(String::from);
+                (userinfo, Some(host), port, path)
+            }
+            // Without an authority a path can't start with "//"; see RFC 3986
+            // section 5.4.2.
+            None if path.starts_with("//") => (None, None, None, Some(format!("/.{path}"))),
+            None => (None, None, None, Some(path)),
+        };
+        Self {
+            scheme: scheme.map(String::from),
+            userinfo: userinfo.map(String::from),
+            host: host.map(String::from),
+            port: port.map(String::from),
+            path,
+            query: query.map(String::from),
+            fragment: fragment.map(String::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Authority<'a> {
+    pub userinfo: Option<&'a str>,
+    pub host:     &'a str,
+    pub port:     Option<&'a str>,
+}
+
+impl<'a> Authority<'a> {
+    pub fn of(uri: &Uri<'a>) -> Option<Self> {
+        Some(Self {
+            userinfo: uri.userinfo,
+            host:     uri.host?,
+            port:     uri.port,
+        })
+    }
+}
+
+/// Merge a relative-path reference with the path of `base`, as described in
+/// [RFC 3986 section 5.2.3].
+///
+/// [RFC 3986 section 5.2.3]: https://datatracker.ietf.org/doc/html/rfc3986#section-5.2.3
+fn merge(base: &Uri, reference_path: &str) -> String {
+    let base_path = base.full_path();
+    if base.host.is_some() && base_path.is_empty() {
+        format!("/{reference_path}")
+    } else {
+        let directory = base_path.rfind('/').map_or("", |i| &base_path[..=i]);
+        format!("{directory}{reference_path}")
+    }
+}
+
+/// A relative-path reference from the directory of `base` to `target`, both
+/// of which must be absolute paths.
+fn relative_path(base: &str, target: &str) -> String {
+    let directory: Vec<_> = base[1..].split('/').collect();
+    let directory = &directory[..directory.len() - 1];
+    let target: Vec<_> = target[1..].split('/').collect();
+    let common = directory
+        .iter()
+        .zip(&target[..target.len() - 1])
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut path = "../".repeat(directory.len() - common);
+    let rest = target[common..].join("/");
+    // Keep the first segment from being read as a scheme or the path from
+    // being read as absolute, and never produce an empty path.
+    let first = rest.split('/').next().unwrap_or_default();
+    if path.is_empty() && (first.is_empty() || first.contains(':')) {
+        path.push_str("./");
+    }
+    path.push_str(&rest);
+    path
+}
+
+/// Interpret and remove the `.` and `..` segments of `path`, as described in
+/// [RFC 3986 section 5.2.4].
+///
+/// [RFC 3986 section 5.2.4]: https://datatracker.ietf.org/doc/html/rfc3986#section-5.2.4
+pub(crate) fn remove_dot_segments(mut input: &str) -> String {
+    fn pop_segment(output: &mut String) {
+        output.truncate(output.rfind('/').unwrap_or(0));
+    }
+
+    let mut output = String::with_capacity(input.len());
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix("../") {
+            input = rest;
+        } else if let Some(rest) = input.strip_prefix("./") {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") {
+            input = &input[3..];
+            pop_segment(&mut output);
+        } else if input == "/.." {
+            input = "/";
+            pop_segment(&mut output);
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let end = input
+                .bytes()
+                .skip(1)
+                .position(|b| b == b'/')
+                .map_or(input.len(), |i| i + 1);
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(reference: &str) -> String {
+        let base = Uri::new("http://a/b/c/d;p?q").unwrap();
+        base.resolve(&Uri::new(reference).unwrap()).to_string()
+    }
+
+    #[test]
+    fn normal_examples() {
+        for (reference, expected) in [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+        ] {
+            assert_eq!(resolve(reference), expected, "{reference:?}");
+        }
+    }
+
+    #[test]
+    fn abnormal_examples() {
+        for (reference, expected) in [
+            ("../../../g", "http://a/g"),
+            ("../../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            (".g", "http://a/b/c/.g"),
+            ("g..", "http://a/b/c/g.."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+            ("g?y/./x", "http://a/b/c/g?y/./x"),
+            ("g?y/../x", "http://a/b/c/g?y/../x"),
+            ("g#s/./x", "http://a/b/c/g#s/./x"),
+            ("g#s/../x", "http://a/b/c/g#s/../x"),
+            ("http:g", "http:g"),
+        ] {
+            assert_eq!(resolve(reference), expected, "{reference:?}");
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        let base = UriOwned::new("http://a").unwrap();
+        assert_eq!(base.join("g").unwrap().to_string(), "http://a/g");
+        assert_eq!(base.join("?q").unwrap().to_string(), "http://a?q");
+
+        let base = UriOwned::new("a:/b").unwrap();
+        assert_eq!(base.join("..//c").unwrap().to_string(), "a:/.//c");
+        assert_eq!(
+            base.join("..//c").unwrap().join(".").unwrap().to_string(),
+            "a:/.//"
+        );
+
+        let base = UriOwned::new("mailto:someone@example.com").unwrap();
+        assert_eq!(
+            base.join("#top").unwrap().to_string(),
+            "mailto:someone@example.com#top"
+        );
+
+        assert!(base.join("http://exa mple/").is_err());
+    }
+
+    #[test]
+    fn relative() {
+        for (base, target, expected) in [
+            ("http://a/b/c/d;p?q", "http://a/b/c/g", "g"),
+            ("http://a/b/c/d;p?q", "http://a/b/g/h", "../g/h"),
+            ("http://a/b/c/d;p?q", "http://a/x", "/x"),
+            ("http://a/b/c/d;p?q", "http://a/b/c/", "./"),
+            ("http://a/b/c/d;p?q", "http://a/b/c/d;p?q", ""),
+            ("http://a/b/c/d;p?q", "http://a/b/c/d;p?q#f", "#f"),
+            ("http://a/b/c/d;p?q", "http://a/b/c/d;p?y", "?y"),
+            ("http://a/b/c/d;p?q", "http://a/b/c/d;p", "d;p"),
+            ("http://a/b/c/d;p?q", "http://a/b/c/a:b", "./a:b"),
+            ("http://a/b/c/d;p?q", "http://a/b/c//x", ".//x"),
+            ("http://a/b/c/d;p?q", "http://a//x", "../..//x"),
+            ("http://a/b/c/d;p?q", "http://a/b/c/./g/../h", "h"),
+            ("http://a/b/c/d;p?q", "http://b/c", "//b/c"),
+            (
+                "http://a/b/c/d;p?q",
+                "http://u@a/b/c/d;p?q",
+                "//u@a/b/c/d;p?q",
+            ),
+            ("http://a/b/c/d;p?q", "http://a", "//a"),
+            (
+                "http://a/b/c/d;p?q",
+                "https://a/b/c/d;p?q",
+                "https://a/b/c/d;p?q",
+            ),
+            ("http://a/b/", "http://a/b", "/b"),
+            ("http://a/b/c/", "http://a/b/c", "../c"),
+            ("http://a", "http://a/x", "x"),
+            ("http://a?q", "http://a", "//a"),
+            ("http://a?q", "http://a?q#f", "#f"),
+            ("mailto:a@b", "mailto:c@d", "mailto:c@d"),
+            ("file:///a/b", "file:///c", "/c"),
+            ("http://a/b/./c", "http://a/b/c?x", "c?x"),
+            ("http:.", "http:?x", "http:?x"),
+        ] {
+            let base = Uri::new(base).unwrap();
+            let target = Uri::new(target).unwrap();
+            let relative = base.make_relative(&target);
+            assert_eq!(relative.to_string(), expected, "{base} -> {target}");
+            assert_eq!(
+                base.resolve(&relative.as_ref()).to_string(),
+                base.resolve(&target).to_string(),
+                "{base} -> {target}"
+            );
+        }
+    }
+
+    #[test]
+    fn dot_segments() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("mid/content=5/../6"), "mid/6");
+        assert_eq!(remove_dot_segments("/.."), "/");
+        assert_eq!(remove_dot_segments(""), "");
+    }
+}
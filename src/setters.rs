@@ -0,0 +1,260 @@
+//! Validating setters for the components of a [`UriOwned`].
+//!
+//! Each setter takes an already percent-encoded value, checks it against the
+//! grammar for its component and adjusts the rest of the URI so that its
+//! [`Display`](std::fmt::Display) output parses back to the same components.
+
+use crate::{Component, Error, UriOwned, parse::validate_component};
+
+impl UriOwned {
+    /// Set or remove the scheme.
+    ///
+    /// Removing the scheme from a URI whose path starts with a segment
+    /// containing `:` prefixes the path with `./`, so that segment isn't read
+    /// as a scheme.
+    ///
+    /// ```
+    /// use uri_rs::UriOwned;
+    ///
+    /// let mut uri = UriOwned::new("urn:isbn:0451450523").unwrap();
+    /// uri.set_scheme(None).unwrap();
+    /// assert_eq!(uri.to_string(), "./isbn:0451450523");
+    /// assert!(uri.set_scheme(Some("1nvalid")).is_err());
+    /// ```
+    pub fn set_scheme(&mut self, scheme: Option<&str>) -> Result<(), Error> {
+        validate(Component::Scheme, scheme)?;
+        self.scheme = scheme.map(String::from);
+        self.fix_relative_path();
+        Ok(())
+    }
+
+    /// Set or remove the userinfo, which must already be percent-encoded.
+    ///
+    /// Setting a userinfo on a URI without an authority gives it an empty
+    /// host.
+    pub fn set_userinfo(&mut self, userinfo: Option<&str>) -> Result<(), Error> {
+        validate(Component::Userinfo, userinfo)?;
+        if userinfo.is_some() {
+            self.ensure_authority();
+        }
+        self.userinfo = userinfo.map(String::from);
+        Ok(())
+    }
+
+    /// Set or remove the host, including the brackets around an IP literal.
+    ///
+    /// Removing the host removes the whole authority, including the userinfo
+    /// and port. Adding a host to a URI with a rootless path makes the path
+    /// absolute.
+    ///
+    /// ```
+    /// use uri_rs::UriOwned;
+    ///
+    /// let mut uri = UriOwned::new("/a/b").unwrap();
+    /// uri.set_host(Some("example.com")).unwrap();
+    /// assert_eq!(uri.to_string(), "//example.com/a/b");
+    ///
+    /// let mut uri = UriOwned::new("file://host//share/x").unwrap();
+    /// uri.set_host(None).unwrap();
+    /// assert_eq!(uri.to_string(), "file:/.//share/x");
+    /// ```
+    pub fn set_host(&mut self, host: Option<&str>) -> Result<(), Error> {
+        validate(Component::Host, host)?;
+        match host {
+            Some(host) => {
+                self.ensure_authority();
+                self.host = Some(host.to_string());
+            }
+            None => self.remove_authority(),
+        }
+        Ok(())
+    }
+
+    /// Set or remove the port.
+    ///
+    /// Setting a port on a URI without an authority gives it an empty host.
+    pub fn set_port(&mut self, port: Option<u16>) {
+        if port.is_some() {
+            self.ensure_authority();
+        }
+        self.port = port.map(|port| port.to_string());
+    }
+
+    /// Replace the path, which must already be percent-encoded.
+    ///
+    /// `path` is the full path as defined by RFC 3986, so a URI with an
+    /// authority expects it to start with `/`; one is added if it is
+    /// missing. A path that could be mistaken for an authority or scheme is
+    /// prefixed with `/.` or `./`.
+    ///
+    /// ```
+    /// use uri_rs::UriOwned;
+    ///
+    /// let mut uri = UriOwned::new("https://example.com").unwrap();
+    /// uri.set_path("docs/index.html").unwrap();
+    /// assert_eq!(uri.to_string(), "https://example.com/docs/index.html");
+    /// assert!(uri.set_path("/a b").is_err());
+    /// ```
+    pub fn set_path(&mut self, path: &str) -> Result<(), Error> {
+        validate(Component::Path, Some(path))?;
+        if self.host.is_some() {
+            self.path =
+                (!path.is_empty()).then(|| path.strip_prefix('/').unwrap_or(path).to_string());
+        } else {
+            self.set_unauthorized_path(path.to_string());
+        }
+        Ok(())
+    }
+
+    /// Set or remove the query, which must already be percent-encoded.
+    pub fn set_query(&mut self, query: Option<&str>) -> Result<(), Error> {
+        validate(Component::Query, query)?;
+        self.query = query.map(String::from);
+        Ok(())
+    }
+
+    /// Set or remove the fragment, which must already be percent-encoded.
+    pub fn set_fragment(&mut self, fragment: Option<&str>) -> Result<(), Error> {
+        validate(Component::Fragment, fragment)?;
+        self.fragment = fragment.map(String::from);
+        Ok(())
+    }
+
+    /// Give the URI an empty host if it has no authority, converting the path
+    /// to the representation used with one.
+    pub(crate) fn ensure_authority(&mut self) {
+        if self.host.is_some() {
+            return;
+        }
+        self.host = Some(String::new());
+        self.path = self
+            .path
+            .take()
+            .filter(|path| !path.is_empty())
+            .map(|path| path.strip_prefix('/').map(String::from).unwrap_or(path));
+    }
+
+    fn remove_authority(&mut self) {
+        if self.host.is_none() {
+            return;
+        }
+        let path = self.as_ref().full_path();
+        self.userinfo = None;
+        self.host = None;
+        self.port = None;
+        self.set_unauthorized_path(path);
+    }
+
+    fn set_unauthorized_path(&mut self, path: String) {
+        self.path = Some(if path.starts_with("//") {
+            format!("/.{path}")
+        } else {
+            path
+        });
+        self.fix_relative_path();
+    }
+
+    /// Keep the first segment of a relative reference's path from being read
+    /// as a scheme.
+    fn fix_relative_path(&mut self) {
+        if self.scheme.is_some() || self.host.is_some() {
+            return;
+        }
+        if let Some(path) = &mut self.path
+            && path.split('/').next().is_some_and(|s| s.contains(':'))
+        {
+            path.insert_str(0, "./");
+        }
+    }
+}
+
+fn validate(component: Component, value: Option<&str>) -> Result<(), Error> {
+    value.map_or(Ok(()), |value| validate_component(component, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply `set` to `uri`, and check that the result survives a round trip
+    /// through its string form.
+    fn set(uri: &str, set: impl FnOnce(&mut UriOwned)) -> String {
+        let mut uri = UriOwned::new(uri).unwrap();
+        set(&mut uri);
+        let s = uri.to_string();
+        assert_eq!(UriOwned::new(&s).unwrap(), uri, "{s:?}");
+        s
+    }
+
+    #[test]
+    fn authority() {
+        assert_eq!(set("a/b", |u| u.set_host(Some("h")).unwrap()), "//h/a/b");
+        assert_eq!(
+            set("x:", |u| u.set_host(Some("[::1]")).unwrap()),
+            "x://[::1]"
+        );
+        assert_eq!(set("x:/", |u| u.set_port(Some(8080))), "x://:8080/");
+        assert_eq!(
+            set("x:", |u| u.set_userinfo(Some("u:p")).unwrap()),
+            "x://u:p@"
+        );
+        assert_eq!(set("x://u@h:1/p", |u| u.set_host(None).unwrap()), "x:/p");
+        assert_eq!(set("x://h", |u| u.set_host(None).unwrap()), "x:");
+        assert_eq!(set("//h//p", |u| u.set_host(None).unwrap()), "/.//p");
+        assert_eq!(set("x://h:1/", |u| u.set_port(None)), "x://h/");
+        assert_eq!(set("x", |u| u.set_username("a:b")), "//a%3Ab@/x");
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(set("x://h", |u| u.set_path("/").unwrap()), "x://h/");
+        assert_eq!(set("x://h/p", |u| u.set_path("").unwrap()), "x://h");
+        assert_eq!(set("x://h", |u| u.set_path("//p").unwrap()), "x://h//p");
+        assert_eq!(set("x:", |u| u.set_path("//p").unwrap()), "x:/.//p");
+        assert_eq!(set("", |u| u.set_path("a:b/c").unwrap()), "./a:b/c");
+        assert_eq!(set("x:a:b", |u| u.set_scheme(None).unwrap()), "./a:b");
+        assert_eq!(set("a/b:c", |u| u.set_scheme(None).unwrap()), "a/b:c");
+    }
+
+    #[test]
+    fn query_and_fragment() {
+        assert_eq!(
+            set("x:/p", |u| u.set_query(Some("a=1&b=/?")).unwrap()),
+            "x:/p?a=1&b=/?"
+        );
+        assert_eq!(set("x:/p?q#f", |u| u.set_query(None).unwrap()), "x:/p#f");
+        assert_eq!(
+            set("x:/p#f", |u| u.set_fragment(Some("")).unwrap()),
+            "x:/p#"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let mut uri = UriOwned::new("http://h/p?q#f").unwrap();
+        let before = uri.clone();
+        assert_eq!(
+            uri.set_scheme(Some("ht tp")),
+            Err(Error::InvalidSchemeChar {
+                offset: 2,
+                ch:     ' ',
+            })
+        );
+        assert_eq!(
+            uri.set_host(Some("[::1")),
+            Err(Error::UnterminatedIpLiteral { offset: 0 })
+        );
+        assert_eq!(
+            uri.set_userinfo(Some("a@b")),
+            Err(Error::InvalidChar {
+                component: Component::Userinfo,
+                offset:    1,
+                ch:        '@',
+            })
+        );
+        assert!(uri.set_path("/a?b").is_err());
+        assert!(uri.set_query(Some("a#b")).is_err());
+        assert!(uri.set_fragment(Some("%")).is_err());
+        assert_eq!(uri, before);
+    }
+}
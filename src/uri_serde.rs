@@ -0,0 +1,270 @@
+//! [`serde`] support for [`Uri`] and [`UriOwned`].
+//!
+//! Both serialize as their string form, and deserializing one validates it
+//! like [`Uri::new`]. A [`Uri`] borrows from the input, so it can only be
+//! deserialized from formats that hand out borrowed strings, such as JSON
+//! text without escapes.
+
+use std::{borrow::Cow, fmt};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, Unexpected, Visitor},
+};
+
+use crate::{Uri, UriOwned};
+
+impl Serialize for Uri<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for UriOwned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Uri<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UriVisitor;
+
+        impl<'de> Visitor<'de> for UriVisitor {
+            type Value = Uri<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a borrowed URI string")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, s: &'de str) -> Result<Uri<'de>, E> {
+                Uri::new(s).map_err(|e| invalid_uri(s, e))
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Uri<'de>, E> {
+                Err(E::invalid_type(Unexpected::Str(s), &self))
+            }
+        }
+
+        deserializer.deserialize_str(UriVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for UriOwned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UriOwnedVisitor;
+
+        impl Visitor<'_> for UriOwnedVisitor {
+            type Value = UriOwned;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a URI string")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<UriOwned, E> {
+                UriOwned::new(s).map_err(|e| invalid_uri(s, e))
+            }
+        }
+
+        deserializer.deserialize_str(UriOwnedVisitor)
+    }
+}
+
+fn invalid_uri<E: de::Error>(s: &str, error: crate::Error) -> E {
+    E::custom(format_args!("invalid URI {s:?}: {error}"))
+}
+
+/// (De)serialize a [`UriOwned`] as a map of its components instead of a
+/// string, for use with `#[serde(with = "uri_rs::serde_components")]`.
+///
+/// Components are still percent-encoded, the path is the full path as
+/// defined by RFC 3986 and an empty port is left out. Deserializing
+/// validates each component as its [setter](UriOwned::set_host) does.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use uri_rs::UriOwned;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "uri_rs::serde_components")]
+///     upstream: UriOwned,
+/// }
+///
+/// let config: Config = toml::from_str(
+///     r#"
+///     [upstream]
+///     scheme = "https"
+///     host = "api.example.com"
+///     port = 8443
+///     path = "/v1"
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(config.upstream.to_string(), "https://api.example.com:8443/v1");
+/// ```
+pub mod components {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Components<'a> {
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        scheme:   Option<Cow<'a, str>>,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        userinfo: Option<Cow<'a, str>>,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        host:     Option<Cow<'a, str>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        port:     Option<u16>,
+        #[serde(borrow, default)]
+        path:     Cow<'a, str>,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        query:    Option<Cow<'a, str>>,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        fragment: Option<Cow<'a, str>>,
+    }
+
+    pub fn serialize<S: Serializer>(uri: &UriOwned, serializer: S) -> Result<S::Ok, S::Error> {
+        let uri = uri.as_ref();
+        Components {
+            scheme:   uri.scheme.map(Cow::Borrowed),
+            userinfo: uri.userinfo.map(Cow::Borrowed),
+            host:     uri.host.map(Cow::Borrowed),
+            port:     uri.port_u16(),
+            path:     Cow::Owned(uri.full_path()),
+            query:    uri.query.map(Cow::Borrowed),
+            fragment: uri.fragment.map(Cow::Borrowed),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UriOwned, D::Error> {
+        let components = Components::deserialize(deserializer)?;
+        let invalid = |e: crate::Error| de::Error::custom(format_args!("invalid URI: {e}"));
+        let mut uri = UriOwned::from_parts(None, None, String::new(), None, None);
+        uri.set_scheme(components.scheme.as_deref())
+            .map_err(invalid)?;
+        uri.set_host(components.host.as_deref()).map_err(invalid)?;
+        if components.userinfo.is_some() {
+            uri.set_userinfo(components.userinfo.as_deref())
+                .map_err(invalid)?;
+        }
+        if components.port.is_some() {
+            uri.set_port(components.port);
+        }
+        uri.set_path(&components.path).map_err(invalid)?;
+        uri.set_query(components.query.as_deref())
+            .map_err(invalid)?;
+        uri.set_fragment(components.fragment.as_deref())
+            .map_err(invalid)?;
+        Ok(uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        upstream: UriOwned,
+        #[serde(default)]
+        mirrors:  Vec<UriOwned>,
+    }
+
+    #[test]
+    fn string_form() {
+        let config: Config = toml::from_str(
+            r#"
+            upstream = "https://user@example.com:8443/a%20b?q#f"
+            mirrors = ["//mirror.example/", "urn:isbn:0451450523"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.upstream,
+            UriOwned::new("https://user@example.com:8443/a%20b?q#f").unwrap()
+        );
+        assert_eq!(config.mirrors[1].scheme.as_deref(), Some("urn"));
+
+        let toml = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<Config>(&toml).unwrap(), config);
+
+        let err = toml::from_str::<Config>(r#"upstream = "http://exa mple/""#).unwrap_err();
+        assert!(
+            err.message()
+                .contains("invalid URI \"http://exa mple/\": invalid character ' ' in host"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn borrowed() {
+        use serde::de::{
+            IntoDeserializer,
+            value::{BorrowedStrDeserializer, Error},
+        };
+
+        let input = String::from("http://example.com/x?y");
+        let uri = Uri::deserialize(BorrowedStrDeserializer::<Error>::new(&input)).unwrap();
+        assert_eq!(uri, Uri::new(&input).unwrap());
+        assert!(std::ptr::eq(uri.host.unwrap(), &input[7..18]));
+
+        let owned: Result<Uri, Error> = Uri::deserialize(input.as_str().into_deserializer());
+        assert!(
+            owned
+                .unwrap_err()
+                .to_string()
+                .contains("expected a borrowed URI string")
+        );
+        let err = Uri::deserialize(BorrowedStrDeserializer::<Error>::new("%zz")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid URI \"%zz\": invalid percent-encoding in path at byte 0"
+        );
+    }
+
+    #[test]
+    fn components() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Config {
+            #[serde(with = "super::components")]
+            upstream: UriOwned,
+        }
+
+        for uri in [
+            "https://user:pw@example.com:8443/a/b?q=1#f",
+            "http://[::1]",
+            "http://example.com/",
+            "mailto:someone@example.com",
+            "/relative/path?x",
+            "file:///etc/hosts",
+        ] {
+            let config = Config {
+                upstream: UriOwned::new(uri).unwrap(),
+            };
+            let toml = toml::to_string(&config).unwrap();
+            assert_eq!(toml::from_str::<Config>(&toml).unwrap(), config, "{toml}");
+        }
+
+        let toml = toml::to_string(&Config {
+            upstream: UriOwned::new("http://example.com:8080/a?q").unwrap(),
+        })
+        .unwrap();
+        assert_eq!(
+            toml,
+            "[upstream]\nscheme = \"http\"\nhost = \"example.com\"\nport = 8080\npath = \"/a\"\nquery = \"q\"\n"
+        );
+
+        let err = toml::from_str::<Config>("[upstream]\nhost = \"a b\"\n").unwrap_err();
+        assert!(
+            err.message()
+                .starts_with("invalid URI: invalid character ' ' in host"),
+            "{err}"
+        );
+        assert!(toml::from_str::<Config>("[upstream]\nhots = \"a\"\n").is_err());
+    }
+}